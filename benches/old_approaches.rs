@@ -0,0 +1,140 @@
+//! Times solutions against the approaches they replaced, checking that
+//! both give the same answers. Run it with `cargo bench`.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use aoc2023::{day1, day4};
+use common::Rng;
+use std::{
+    collections::HashSet,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+const RUNS: u32 = 5;
+
+fn main() {
+    day1_words();
+    day4_matches();
+}
+
+// the fastest of a few runs, which is the least disturbed by everything else
+fn time<T>(mut f: impl FnMut() -> T) -> (T, Duration) {
+    let mut best = Duration::MAX;
+    let mut out = None;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        out = Some(black_box(f()));
+        best = best.min(start.elapsed());
+    }
+    (out.unwrap(), best)
+}
+
+fn report(name: &str, old: Duration, new: Duration) {
+    println!("{name}: {old:?} before, {new:?} after");
+}
+
+// the chained replacements day 1 part 2 used before the single scan
+fn replace_in_order(s: &str) -> String {
+    s.replace("one", "one1one")
+        .replace("two", "two2two")
+        .replace("three", "three3three")
+        .replace("four", "four4four")
+        .replace("five", "five5five")
+        .replace("six", "six6six")
+        .replace("seven", "seven7seven")
+        .replace("eight", "eight8eight")
+        .replace("nine", "nine9nine")
+}
+
+fn old_day1_part2(input: &str) -> u64 {
+    input
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let replaced = replace_in_order(line);
+            let mut digits = replaced.chars().filter_map(|ch| ch.to_digit(10));
+            let first = digits.next().unwrap();
+            let last = digits.next_back().unwrap_or(first);
+            10 * first as u64 + last as u64
+        })
+        .sum()
+}
+
+// long lines full of words, many of them overlapping, with some noise
+fn day1_input(rng: &mut Rng) -> String {
+    const PIECES: [&str; 16] = [
+        "one",
+        "two",
+        "three",
+        "four",
+        "five",
+        "six",
+        "seven",
+        "eight",
+        "nine",
+        "oneight",
+        "eightwo",
+        "twone",
+        "sevenine",
+        "eightwoneight",
+        "x",
+        "7",
+    ];
+    let mut input = String::new();
+    for _ in 0..2000 {
+        for _ in 0..rng.below(200) + 1 {
+            input.push_str(rng.pick(&PIECES));
+        }
+        input.push('\n');
+    }
+    // and a few very long lines that are nothing but overlaps
+    for _ in 0..10 {
+        input.push_str(&"eightwoneight".repeat(10_000));
+        input.push('\n');
+    }
+    input
+}
+
+fn day1_words() {
+    let input = day1_input(&mut Rng(0x2023_1201));
+    let (old, old_time) = time(|| old_day1_part2(&input));
+    let (new, new_time) = time(|| day1::part2(input.as_bytes()).unwrap());
+    assert_eq!(old, new);
+    report("day 1 part 2", old_time, new_time);
+}
+
+// the winning and scratched numbers as hash sets, as scratchcards kept
+// them before using bitsets
+fn day4_sets(input: &str) -> Vec<(HashSet<usize>, HashSet<usize>)> {
+    input
+        .lines()
+        .map(|line| {
+            let (_, numbers) = line.split_once(':').unwrap();
+            let (winning, scratched) = numbers.split_once('|').unwrap();
+            let set = |list: &str| {
+                list.split_ascii_whitespace()
+                    .map(|num| num.parse().unwrap())
+                    .collect()
+            };
+            (set(winning), set(scratched))
+        })
+        .collect()
+}
+
+fn day4_matches() {
+    let input = common::read_string("day4/input.txt").unwrap();
+    let sets = day4_sets(&input);
+    let cards = day4::parse_cards(&input).unwrap();
+    let (old, old_time) = time(|| -> Vec<usize> {
+        let sets = black_box(&sets);
+        sets.iter()
+            .map(|(winning, scratched)| winning.intersection(scratched).count())
+            .collect()
+    });
+    let (new, new_time) =
+        time(|| -> Vec<usize> { black_box(&cards).iter().map(day4::Card::matches).collect() });
+    assert_eq!(old, new);
+    report("day 4 matches", old_time, new_time);
+}
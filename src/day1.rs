@@ -1,64 +1,323 @@
-use miette::{miette, Context, IntoDiagnostic};
-use std::io::{BufRead, BufReader, Read};
+use crate::{
+    errors::{GenericError, GenericWarning, ParseError},
+    parse::lines_with_offsets,
+};
+use miette::{ensure, Context, Diagnostic, IntoDiagnostic, LabeledSpan, Severity, SourceCode};
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read},
+    ops::Range,
+};
+use thiserror::Error;
+
+const DIGITS: [(&str, u32); 10] = [
+    ("0", 0),
+    ("1", 1),
+    ("2", 2),
+    ("3", 3),
+    ("4", 4),
+    ("5", 5),
+    ("6", 6),
+    ("7", 7),
+    ("8", 8),
+    ("9", 9),
+];
+
+const WORDS: [(&str, u32); 9] = [
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
 
 pub fn part1(input: impl Read) -> miette::Result<u64> {
-    let reader = BufReader::new(input);
+    calibrate(input, &Vocabulary::digits())
+}
+
+pub fn part2(input: impl Read) -> miette::Result<u64> {
+    calibrate(input, &Vocabulary::english())
+}
+
+/// A set of tokens that each stand for a single decimal digit.
+#[derive(Debug, Clone)]
+pub struct Vocabulary {
+    // sorted longest first, so the longest token wins when several match
+    tokens: Vec<(String, u32)>,
+}
+
+impl Vocabulary {
+    /// Builds a vocabulary, rejecting empty tokens, values that aren't a
+    /// single digit and tokens mapped to more than one digit.
+    pub fn new<S: Into<String>>(
+        tokens: impl IntoIterator<Item = (S, u32)>,
+    ) -> miette::Result<Self> {
+        let mut digits: HashMap<String, u32> = HashMap::new();
+        for (token, digit) in tokens {
+            let token = token.into();
+            ensure!(!token.is_empty(), "empty token for digit {digit}");
+            ensure!(
+                digit <= 9,
+                "token {token} maps to {digit}, which is not a digit"
+            );
+            if let Some(&other) = digits.get(&token) {
+                ensure!(
+                    other == digit,
+                    "token {token} is ambiguous, it maps to both {other} and {digit}"
+                );
+            }
+            digits.insert(token, digit);
+        }
+        let mut tokens: Vec<_> = digits.into_iter().collect();
+        tokens.sort_unstable_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+        Ok(Self { tokens })
+    }
+
+    /// Just the decimal digits, as in part 1.
+    pub fn digits() -> Self {
+        Self::new(DIGITS).expect("digits are a valid vocabulary")
+    }
+
+    /// Decimal digits and English words for one to nine, as in part 2.
+    pub fn english() -> Self {
+        Self::new(DIGITS.into_iter().chain(WORDS)).expect("english is a valid vocabulary")
+    }
+
+    pub fn tokens(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.tokens
+            .iter()
+            .map(|(token, digit)| (token.as_str(), *digit))
+    }
+
+    /// Finds the token starting at `pos`, returning its digit and length.
+    fn token_at(&self, line: &[u8], pos: usize) -> Option<(u32, usize)> {
+        let rest = &line[pos..];
+        self.tokens.iter().find_map(|(token, digit)| {
+            rest.starts_with(token.as_bytes())
+                .then_some((*digit, token.len()))
+        })
+    }
+
+    /// Finds the first and last tokens in `line`, which starts at byte `offset` of the input.
+    fn first_and_last(&self, line: &str, offset: usize) -> Option<(Token, Token)> {
+        let line = line.as_bytes();
+        let token = |pos| {
+            self.token_at(line, pos).map(|(digit, len)| Token {
+                digit,
+                span: offset + pos..offset + pos + len,
+            })
+        };
+        // tokens may overlap (e.g. "eightwo"), so the first and last are
+        // searched for independently, from opposite ends of the line
+        let first = (0..line.len()).find_map(token)?;
+        let last = (0..line.len())
+            .rev()
+            .find_map(token)
+            .expect("if first is set, last should be too");
+        Some((first, last))
+    }
+}
+
+/// A digit token matched in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub digit: u32,
+    /// Byte range of the token in the whole input.
+    pub span: Range<usize>,
+}
+
+/// How a single line was calibrated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calibration {
+    /// Zero-based line number.
+    pub line: usize,
+    pub first: Token,
+    pub last: Token,
+}
+
+impl Calibration {
+    pub fn value(&self) -> u32 {
+        10 * self.first.digit + self.last.digit
+    }
+}
+
+/// Per-line calibrations of an input.
+///
+/// As a [`Diagnostic`], it renders the input with the first and last token
+/// of every line labelled.
+#[derive(Debug, Error)]
+#[error("calibration values sum to {}", self.sum())]
+pub struct Breakdown {
+    source_code: String,
+    lines: Vec<Calibration>,
+    warnings: Vec<GenericWarning>,
+}
+
+impl Breakdown {
+    pub fn lines(&self) -> &[Calibration] {
+        &self.lines
+    }
+
+    /// Whether any lines were skipped in lenient mode.
+    pub fn has_warnings(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
+    pub fn sum(&self) -> u64 {
+        self.lines.iter().map(|line| line.value() as u64).sum()
+    }
+}
+
+impl Diagnostic for Breakdown {
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Advice)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source_code)
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        Some(Box::new(
+            self.warnings
+                .iter()
+                .map(|warning| warning as &dyn Diagnostic),
+        ))
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(self.lines.iter().flat_map(|line| {
+            let value = line.value();
+            if line.first.span == line.last.span {
+                let text = format!("first and last: {value}");
+                vec![LabeledSpan::new_with_span(
+                    Some(text),
+                    line.first.span.clone(),
+                )]
+            } else {
+                let (first, last) = (line.first.digit, line.last.digit);
+                vec![
+                    LabeledSpan::new_with_span(
+                        Some(format!("first: {first}")),
+                        line.first.span.clone(),
+                    ),
+                    LabeledSpan::new_with_span(
+                        Some(format!("last: {last} => {value}")),
+                        line.last.span.clone(),
+                    ),
+                ]
+            }
+        })))
+    }
+}
+
+/// Sums the calibration values of every line, where tokens come from `vocabulary`.
+///
+/// Lines are read one at a time and only the sum is kept. Fails if any line
+/// has no tokens, reporting all such lines at once.
+pub fn calibrate(input: impl Read, vocabulary: &Vocabulary) -> miette::Result<u64> {
+    let mut reader = BufReader::new(input);
+    let mut buf = String::new();
     let mut sum = 0;
-    for (num, line) in reader.lines().enumerate() {
-        let line = line
+    // just the lines without tokens, for the error to point at
+    let mut invalid = String::new();
+    let mut errors = Vec::new();
+    for num in 0.. {
+        buf.clear();
+        let read = reader
+            .read_line(&mut buf)
             .into_diagnostic()
             .wrap_err_with(|| format!("failed to read line {num}"))?;
+        if read == 0 {
+            break;
+        }
+        let line = buf.strip_suffix('\n').unwrap_or(&buf);
+        let line = line.strip_suffix('\r').unwrap_or(line);
         if line.is_empty() {
             continue;
         }
-        let (mut first, mut last) = (None, None);
-        for ch in line.chars() {
-            if let Some(digit) = ch.to_digit(10) {
-                first.get_or_insert(digit);
-                last = digit.into();
+        match vocabulary.first_and_last(line, 0) {
+            Some((first, last)) => sum += 10 * first.digit as u64 + last.digit as u64,
+            None => {
+                let span = invalid.len()..invalid.len() + line.len();
+                errors.push(GenericError::new(
+                    format!("no valid digits in line {num}"),
+                    span,
+                ));
+                invalid.push_str(line);
+                invalid.push('\n');
             }
         }
-        let first = first.ok_or_else(|| miette!("no valid digits in line {num}"))?;
-        let last = last.expect("if first is set, last should be too");
-        sum += 10 * first as u64 + last as u64;
     }
-    Ok(sum)
+    if errors.is_empty() {
+        Ok(sum)
+    } else {
+        Err(ParseError::new(invalid, errors.into_iter()).into())
+    }
 }
 
-// this is dumb, but it's probably the only algorithm that works as intended
-fn replace_in_order(s: &str) -> String {
-    s.replace("one", "one1one")
-        .replace("two", "two2two")
-        .replace("three", "three3three")
-        .replace("four", "four4four")
-        .replace("five", "five5five")
-        .replace("six", "six6six")
-        .replace("seven", "seven7seven")
-        .replace("eight", "eight8eight")
-        .replace("nine", "nine9nine")
+/// Calibrates every line, keeping track of which tokens were matched where.
+///
+/// Fails if any line has no tokens, reporting all such lines at once.
+pub fn breakdown(input: impl Read, vocabulary: &Vocabulary) -> miette::Result<Breakdown> {
+    let (breakdown, invalid) = scan(input, vocabulary)?;
+    if invalid.is_empty() {
+        Ok(breakdown)
+    } else {
+        let errors = invalid
+            .into_iter()
+            .map(|(message, span)| GenericError::new(message, span));
+        Err(ParseError::new(breakdown.source_code, errors).into())
+    }
 }
 
-pub fn part2(input: impl Read) -> miette::Result<u64> {
-    let reader = BufReader::new(input);
-    let mut sum = 0;
-    for (num, line) in reader.lines().enumerate() {
-        let line = line
-            .into_diagnostic()
-            .wrap_err_with(|| format!("failed to read line {num}"))?;
+/// Like [`breakdown`], but skips lines without tokens, keeping them as warnings.
+pub fn breakdown_lenient(input: impl Read, vocabulary: &Vocabulary) -> miette::Result<Breakdown> {
+    let (mut breakdown, invalid) = scan(input, vocabulary)?;
+    breakdown.warnings = invalid
+        .into_iter()
+        .map(|(message, span)| GenericWarning::new(message, span))
+        .collect();
+    Ok(breakdown)
+}
+
+type Invalid = (String, Range<usize>);
+
+fn scan(
+    mut input: impl Read,
+    vocabulary: &Vocabulary,
+) -> miette::Result<(Breakdown, Vec<Invalid>)> {
+    let mut source_code = String::new();
+    input
+        .read_to_string(&mut source_code)
+        .into_diagnostic()
+        .wrap_err("failed to read input")?;
+    let mut lines = Vec::new();
+    let mut invalid = Vec::new();
+    for (num, (offset, line)) in lines_with_offsets(&source_code).enumerate() {
         if line.is_empty() {
             continue;
         }
-        let replaced = replace_in_order(&line);
-        let (mut first, mut last) = (None, None);
-        for ch in replaced.chars() {
-            if let Some(digit) = ch.to_digit(10) {
-                first.get_or_insert(digit);
-                last = digit.into();
-            }
+        match vocabulary.first_and_last(line, offset) {
+            Some((first, last)) => lines.push(Calibration {
+                line: num,
+                first,
+                last,
+            }),
+            None => invalid.push((
+                format!("no valid digits in line {num}"),
+                offset..offset + line.len(),
+            )),
         }
-        let first = first.ok_or_else(|| miette!("no valid digits in line {num}"))?;
-        let last = last.expect("if first is set, last should be too");
-        sum += 10 * first as u64 + last as u64;
     }
-    Ok(sum)
+    let breakdown = Breakdown {
+        source_code,
+        lines,
+        warnings: Vec::new(),
+    };
+    Ok((breakdown, invalid))
 }
@@ -1,49 +1,176 @@
 pub mod chumsky;
+pub mod feasibility;
+pub mod inference;
 pub mod manual;
+pub mod streaming;
+
+use miette::ensure;
+use std::collections::{BTreeMap, BTreeSet};
 
 // default implementation
-pub use self::chumsky::{part1, part2};
+pub use self::chumsky::{parse_games, part1, part2};
 
-#[derive(Debug, Default, PartialEq, Eq)]
-struct Game {
+/// A game, as read from a single line.
+///
+/// Every parser accepts exactly the same lines, where `ws` is ASCII
+/// whitespace and colours are made of alphabetic characters, and rejects
+/// any draw with more than `usize::MAX` cubes of a colour:
+///
+/// ```text
+/// line = ws* "Game" ws+ digits ":" ws+ draw (";" ws+ draw)* ws*
+/// draw = cube ("," ws+ cube)*
+/// cube = digits ws+ color
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Game {
     id: usize,
     draws: Vec<ColorSet>,
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+/// Counts of cubes by colour name.
+///
+/// Colours with no cubes are never stored, so sets compare equal regardless
+/// of whether a colour was omitted or given a zero count.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ColorSet {
-    red: usize,
-    green: usize,
-    blue: usize,
+    counts: BTreeMap<String, usize>,
 }
 
 impl ColorSet {
     pub fn new(red: usize, green: usize, blue: usize) -> Self {
-        Self { red, blue, green }
+        Self::from_counts([("red", red), ("green", green), ("blue", blue)])
+    }
+
+    /// Builds a set from colour counts, adding up colours given more than once.
+    ///
+    /// Panics if the counts of a colour add up to more than `usize::MAX`.
+    pub fn from_counts<S: Into<String>>(counts: impl IntoIterator<Item = (S, usize)>) -> Self {
+        let mut set = Self::default();
+        for (color, count) in counts {
+            set.add(color, count).expect("cube count overflowed");
+        }
+        set
+    }
+
+    /// Number of cubes of `color`, which is zero for colours not in the set.
+    pub fn get(&self, color: &str) -> usize {
+        self.counts.get(color).copied().unwrap_or_default()
     }
 
-    fn is_empty(&self) -> bool {
-        self.red == 0 && self.green == 0 && self.blue == 0
+    /// Colours with at least one cube, with their counts.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.counts
+            .iter()
+            .map(|(color, &count)| (color.as_str(), count))
     }
 
-    fn power(&self) -> usize {
-        self.red * self.green * self.blue
+    /// Product of the counts of every colour in `palette`.
+    pub fn power(&self, palette: &Palette) -> usize {
+        palette.iter().map(|color| self.get(color)).product()
+    }
+
+    /// Whether every colour has at most as many cubes as in `other`.
+    pub fn is_subset(&self, other: &ColorSet) -> bool {
+        self.iter().all(|(color, count)| count <= other.get(color))
+    }
+
+    /// The smallest set containing both `self` and `other`.
+    pub fn union(&self, other: &ColorSet) -> ColorSet {
+        let mut union = self.clone();
+        for (color, count) in other.iter() {
+            let max = union.counts.entry(color.to_owned()).or_default();
+            *max = (*max).max(count);
+        }
+        union
+    }
+
+    // adds `count` cubes of `color`, or returns `None` if its count overflows
+    fn add(&mut self, color: impl Into<String>, count: usize) -> Option<()> {
+        if count > 0 {
+            let total = self.counts.entry(color.into()).or_default();
+            *total = total.checked_add(count)?;
+        }
+        Some(())
     }
 }
 
-impl Game {
-    fn cover(&self) -> ColorSet {
-        let mut cover = ColorSet::default();
-        for draw in &self.draws {
-            cover.red = cover.red.max(draw.red);
-            cover.green = cover.green.max(draw.green);
-            cover.blue = cover.blue.max(draw.blue);
+/// The set of colour names games may use.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Palette {
+    colors: BTreeSet<String>,
+}
+
+impl Palette {
+    pub fn new<S: Into<String>>(colors: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            colors: colors.into_iter().map(Into::into).collect(),
         }
-        cover
     }
 
-    fn is_possible(&self, bag: &ColorSet) -> bool {
-        let cover = self.cover();
-        cover.red <= bag.red && cover.green <= bag.green && cover.blue <= bag.blue
+    /// The colours of the original puzzle.
+    pub fn rgb() -> Self {
+        Self::new(["red", "green", "blue"])
     }
+
+    /// Every colour drawn in any of `games`.
+    pub fn discover<'g>(games: impl IntoIterator<Item = &'g Game>) -> Self {
+        let colors = games
+            .into_iter()
+            .flat_map(|game| &game.draws)
+            .flat_map(|draw| draw.counts.keys().cloned());
+        Self {
+            colors: colors.collect(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.colors.iter().map(String::as_str)
+    }
+
+    pub fn contains(&self, color: &str) -> bool {
+        self.colors.contains(color)
+    }
+
+    fn check(&self, game: &Game) -> miette::Result<()> {
+        for draw in &game.draws {
+            for (color, _) in draw.iter() {
+                ensure!(self.contains(color), "unknown color: {color}");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Game {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn draws(&self) -> &[ColorSet] {
+        &self.draws
+    }
+
+    /// The smallest bag that makes this game possible.
+    pub fn cover(&self) -> ColorSet {
+        self.draws
+            .iter()
+            .fold(ColorSet::default(), |cover, draw| cover.union(draw))
+    }
+
+    pub fn is_possible(&self, bag: &ColorSet) -> bool {
+        self.cover().is_subset(bag)
+    }
+}
+
+fn part1_with(games: &[Game], bag: &ColorSet) -> usize {
+    games
+        .iter()
+        .filter(|game| game.is_possible(bag))
+        .map(|game| game.id)
+        .sum()
+}
+
+fn part2_with(games: &[Game]) -> usize {
+    let palette = Palette::discover(games);
+    games.iter().map(|game| game.cover().power(&palette)).sum()
 }
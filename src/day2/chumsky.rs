@@ -1,70 +1,78 @@
-use super::{ColorSet, Game};
+use super::{part1_with, part2_with, ColorSet, Game, Palette};
 use crate::parse::parse;
 use chumsky::{prelude::*, text};
 use miette::Context;
 
 pub fn part1(input: &str, bag: &ColorSet) -> miette::Result<usize> {
-    let mut sum = 0;
-    for (num, line) in input.lines().enumerate() {
-        let game = parse(line, game()).wrap_err_with(|| format!("at line {num}"))?;
-        if game.is_possible(bag) {
-            sum += game.id;
-        }
-    }
-    Ok(sum)
+    let games = parse_games(input, None)?;
+    Ok(part1_with(&games, bag))
 }
 
 pub fn part2(input: &str) -> miette::Result<usize> {
-    let mut sum = 0;
+    let games = parse_games(input, None)?;
+    Ok(part2_with(&games))
+}
+
+/// Parses one game per line, accepting any colour unless a `palette` is given.
+pub fn parse_games(input: &str, palette: Option<&Palette>) -> miette::Result<Vec<Game>> {
+    let mut games = Vec::new();
     for (num, line) in input.lines().enumerate() {
         let game = parse(line, game()).wrap_err_with(|| format!("at line {num}"))?;
-        let cover = game.cover();
-        sum += cover.power();
+        if let Some(palette) = palette {
+            palette
+                .check(&game)
+                .wrap_err_with(|| format!("at line {num}"))?;
+        }
+        games.push(game);
     }
-    Ok(sum)
-}
-
-#[derive(Clone, Copy, Debug)]
-enum Color {
-    Red,
-    Green,
-    Blue,
+    Ok(games)
 }
 
 fn game<'a>() -> impl Parser<'a, &'a str, Game, extra::Err<Rich<'a, char>>> {
-    let head = just("Game").padded();
+    let whitespace = any().filter(char::is_ascii_whitespace).repeated();
+
+    let separator = whitespace.at_least(1);
+
+    let head = whitespace.then(just("Game")).then(separator);
 
-    let integer = text::int(10).from_str().unwrapped().padded();
+    let integer = text::digits(10).to_slice().try_map(|digits: &str, span| {
+        digits
+            .parse::<usize>()
+            .map_err(|err| Rich::custom(span, err))
+    });
 
-    let id = integer.then_ignore(just(':')).padded();
+    let id = integer.then_ignore(just(':')).then_ignore(separator);
 
-    let color = choice((
-        just("red").to(Color::Red),
-        just("green").to(Color::Green),
-        just("blue").to(Color::Blue),
-    ));
+    let color = any()
+        .filter(|ch: &char| ch.is_alphabetic())
+        .repeated()
+        .at_least(1)
+        .to_slice();
 
-    let color_count = integer.then(color);
+    let color_count = integer.then_ignore(separator).then(color);
 
     let color_set = color_count
-        .separated_by(just(','))
+        .separated_by(just(',').then(separator))
+        .at_least(1)
         .collect()
-        .map(|color_counts: Vec<_>| {
+        .try_map(|color_counts: Vec<(usize, &str)>, span| {
             let mut set = ColorSet::default();
             for (count, color) in color_counts {
-                match color {
-                    Color::Red => set.red += count,
-                    Color::Green => set.green += count,
-                    Color::Blue => set.blue += count,
-                }
+                set.add(color, count).ok_or_else(|| {
+                    Rich::custom(span, format!("too many {color} cubes in one draw"))
+                })?;
             }
-            set
+            Ok(set)
         });
 
-    let color_sets = color_set.separated_by(just(';')).collect();
+    let color_sets = color_set
+        .separated_by(just(';').then(separator))
+        .at_least(1)
+        .collect();
 
     head.ignore_then(id)
         .then(color_sets)
+        .then_ignore(whitespace)
         .then_ignore(end())
         .map(|(id, draws)| Game { id, draws })
 }
@@ -81,21 +89,9 @@ mod tests {
         let expected = Game {
             id: 1,
             draws: vec![
-                ColorSet {
-                    red: 4,
-                    green: 0,
-                    blue: 3,
-                },
-                ColorSet {
-                    red: 1,
-                    green: 2,
-                    blue: 6,
-                },
-                ColorSet {
-                    red: 0,
-                    green: 2,
-                    blue: 0,
-                },
+                ColorSet::new(4, 0, 3),
+                ColorSet::new(1, 2, 6),
+                ColorSet::new(0, 2, 0),
             ],
         };
         assert_eq!(game, expected);
@@ -0,0 +1,83 @@
+use super::{ColorSet, Game};
+use std::collections::BTreeSet;
+
+/// Answers which games are possible for many bags, computing each game's cover once.
+pub struct Feasibility<'g> {
+    games: &'g [Game],
+    covers: Vec<ColorSet>,
+}
+
+impl<'g> Feasibility<'g> {
+    pub fn new(games: &'g [Game]) -> Self {
+        let covers = games.iter().map(Game::cover).collect();
+        Self { games, covers }
+    }
+
+    /// Ids of the games that are possible with `bag`.
+    pub fn possible(&self, bag: &ColorSet) -> Vec<usize> {
+        self.possible_indices(bag)
+            .map(|idx| self.games[idx].id)
+            .collect()
+    }
+
+    /// Ids of the games that are possible with each of `bags`, in the same order.
+    pub fn possible_for_each<'b>(
+        &self,
+        bags: impl IntoIterator<Item = &'b ColorSet>,
+    ) -> Vec<Vec<usize>> {
+        bags.into_iter().map(|bag| self.possible(bag)).collect()
+    }
+
+    /// The smallest bag with which every game is possible.
+    pub fn smallest_bag(&self) -> ColorSet {
+        self.union(0..self.covers.len())
+    }
+
+    /// The minimal bags with which exactly `n` games are possible.
+    ///
+    /// Any such bag contains the union of the covers of those `n` games, so
+    /// the frontier is made of unions that don't let any other game through.
+    /// These are found by growing sets of games one game at a time, closing
+    /// each set under "possible with the union of its covers", and never
+    /// growing a set beyond `n` games.
+    pub fn frontier(&self, n: usize) -> Vec<ColorSet> {
+        let mut seen = BTreeSet::new();
+        let mut stack = vec![self.closure(std::iter::empty())];
+        let mut frontier = BTreeSet::new();
+        while let Some(set) = stack.pop() {
+            if set.len() > n || !seen.insert(set.clone()) {
+                continue;
+            }
+            if set.len() == n {
+                frontier.insert(self.union(set.iter().copied()));
+                continue;
+            }
+            for idx in 0..self.covers.len() {
+                if !set.contains(&idx) {
+                    stack.push(self.closure(set.iter().copied().chain([idx])));
+                }
+            }
+        }
+        frontier.into_iter().collect()
+    }
+
+    fn possible_indices<'a>(&'a self, bag: &'a ColorSet) -> impl Iterator<Item = usize> + 'a {
+        self.covers
+            .iter()
+            .enumerate()
+            .filter(move |(_, cover)| cover.is_subset(bag))
+            .map(|(idx, _)| idx)
+    }
+
+    fn union(&self, indices: impl IntoIterator<Item = usize>) -> ColorSet {
+        indices.into_iter().fold(ColorSet::default(), |union, idx| {
+            union.union(&self.covers[idx])
+        })
+    }
+
+    // indices of every game possible with the union of the given games' covers
+    fn closure(&self, indices: impl IntoIterator<Item = usize>) -> Vec<usize> {
+        let bag = self.union(indices);
+        self.possible_indices(&bag).collect()
+    }
+}
@@ -0,0 +1,132 @@
+//! Maximum-likelihood estimation of bag contents.
+//!
+//! Each draw is modelled as a handful of cubes taken from the bag without
+//! replacement and put back before the next draw. Given the size of a
+//! handful, the chance of seeing a particular mix of colours then follows a
+//! multivariate hypergeometric distribution, and the likelihood of a bag is
+//! the product of those chances over every draw.
+
+use super::{ColorSet, Game, Palette};
+
+/// A candidate bag along with the log-likelihood of the observed draws.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Estimate {
+    pub bag: ColorSet,
+    pub log_likelihood: f64,
+}
+
+/// Natural log of the probability of drawing `draw` from `bag` in one handful.
+///
+/// Impossible draws have a log-likelihood of negative infinity.
+pub fn draw_log_likelihood(draw: &ColorSet, bag: &ColorSet) -> f64 {
+    if !draw.is_subset(bag) {
+        return f64::NEG_INFINITY;
+    }
+    let total = bag.iter().map(|(_, count)| count).sum();
+    let drawn = draw.iter().map(|(_, count)| count).sum();
+    let ways: f64 = draw
+        .iter()
+        .map(|(color, count)| ln_choose(bag.get(color), count))
+        .sum();
+    ways - ln_choose(total, drawn)
+}
+
+/// Natural log of the probability of every draw in `game` coming from `bag`.
+pub fn log_likelihood(game: &Game, bag: &ColorSet) -> f64 {
+    game.draws
+        .iter()
+        .map(|draw| draw_log_likelihood(draw, bag))
+        .sum()
+}
+
+/// Every bag over `palette` that contains at least `min` and holds at most `max_total` cubes.
+pub fn candidates(palette: &Palette, min: &ColorSet, max_total: usize) -> Vec<ColorSet> {
+    let colors: Vec<_> = palette.iter().collect();
+    let mut bags = Vec::new();
+    let mut counts = Vec::with_capacity(colors.len());
+    let base = colors.iter().map(|color| min.get(color)).sum::<usize>();
+    if base <= max_total {
+        extend_candidates(&colors, min, max_total - base, &mut counts, &mut bags);
+    }
+    bags
+}
+
+fn extend_candidates(
+    colors: &[&str],
+    min: &ColorSet,
+    spare: usize,
+    counts: &mut Vec<usize>,
+    bags: &mut Vec<ColorSet>,
+) {
+    let Some(&color) = colors.get(counts.len()) else {
+        let counts = colors.iter().copied().zip(counts.iter().copied());
+        bags.push(ColorSet::from_counts(counts));
+        return;
+    };
+    for extra in 0..=spare {
+        counts.push(min.get(color) + extra);
+        extend_candidates(colors, min, spare - extra, counts, bags);
+        counts.pop();
+    }
+}
+
+/// The most likely of `candidates` for `games`, keeping the earliest on ties.
+///
+/// Candidates that cannot produce every draw are skipped, so there is no
+/// estimate if none of them can.
+pub fn most_likely<'g>(
+    games: impl IntoIterator<Item = &'g Game> + Clone,
+    candidates: impl IntoIterator<Item = ColorSet>,
+) -> Option<Estimate> {
+    let mut best: Option<Estimate> = None;
+    for bag in candidates {
+        let log_likelihood: f64 = games
+            .clone()
+            .into_iter()
+            .map(|game| log_likelihood(game, &bag))
+            .sum();
+        // a bag that cannot produce the draws is no estimate at all
+        if !log_likelihood.is_finite() {
+            continue;
+        }
+        if let Some(best) = &best {
+            if log_likelihood <= best.log_likelihood {
+                continue;
+            }
+        }
+        best = Some(Estimate {
+            bag,
+            log_likelihood,
+        });
+    }
+    best
+}
+
+/// The most likely bag over `palette` with at most `max_total` cubes for a single game.
+pub fn most_likely_bag(game: &Game, palette: &Palette, max_total: usize) -> Option<Estimate> {
+    let bags = candidates(palette, &game.cover(), max_total);
+    most_likely([game], bags)
+}
+
+/// The most likely single bag over `palette` with at most `max_total` cubes for all `games`.
+pub fn most_likely_shared_bag(
+    games: &[Game],
+    palette: &Palette,
+    max_total: usize,
+) -> Option<Estimate> {
+    let cover = games.iter().fold(ColorSet::default(), |cover, game| {
+        cover.union(&game.cover())
+    });
+    let bags = candidates(palette, &cover, max_total);
+    most_likely(games, bags)
+}
+
+fn ln_choose(n: usize, k: usize) -> f64 {
+    if k > n {
+        return f64::NEG_INFINITY;
+    }
+    let k = k.min(n - k);
+    (0..k)
+        .map(|i| ((n - i) as f64).ln() - ((i + 1) as f64).ln())
+        .sum()
+}
@@ -1,28 +1,30 @@
-use super::{ColorSet, Game};
+use super::{part1_with, part2_with, ColorSet, Game, Palette};
 use miette::{bail, ensure, miette, Context, IntoDiagnostic};
-use std::str::FromStr;
 
 pub fn part1(input: &str, bag: &ColorSet) -> miette::Result<usize> {
-    let mut sum = 0;
-    for (num, line) in input.lines().enumerate() {
-        let game =
-            parse_game(line).wrap_err_with(|| format!("failed to parse game at line {num}"))?;
-        if game.is_possible(bag) {
-            sum += game.id;
-        }
-    }
-    Ok(sum)
+    let games = parse_games(input, None)?;
+    Ok(part1_with(&games, bag))
 }
 
 pub fn part2(input: &str) -> miette::Result<usize> {
-    let mut sum = 0;
+    let games = parse_games(input, None)?;
+    Ok(part2_with(&games))
+}
+
+/// Parses one game per line, accepting any colour unless a `palette` is given.
+pub fn parse_games(input: &str, palette: Option<&Palette>) -> miette::Result<Vec<Game>> {
+    let mut games = Vec::new();
     for (num, line) in input.lines().enumerate() {
         let game =
             parse_game(line).wrap_err_with(|| format!("failed to parse game at line {num}"))?;
-        let cover = game.cover();
-        sum += cover.power();
+        if let Some(palette) = palette {
+            palette
+                .check(&game)
+                .wrap_err_with(|| format!("invalid game at line {num}"))?;
+        }
+        games.push(game);
     }
-    Ok(sum)
+    Ok(games)
 }
 
 fn parse_game(s: &str) -> miette::Result<Game> {
@@ -40,100 +42,89 @@ fn parse_game(s: &str) -> miette::Result<Game> {
 }
 
 fn parse_id(s: &str) -> miette::Result<usize> {
-    for (pos, ch) in s.chars().enumerate() {
-        if ch.is_ascii_digit() {
-            continue;
-        }
-        if ch == ':' {
-            return s[..pos]
-                .parse()
-                .into_diagnostic()
-                .wrap_err("failed to parse as usize");
-        }
-        return Err(miette!("invalid character {ch} at position {pos}"));
-    }
-    Err(miette!("missing terminator :"))
+    let id = s
+        .strip_suffix(':')
+        .ok_or_else(|| miette!("missing terminator :"))?;
+    parse_integer(id)
 }
 
-fn parse_draws<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> miette::Result<Vec<ColorSet>> {
-    let mut draws = Vec::new();
-    while let Some(draw) = parse_draw(tokens)? {
-        draws.push(draw);
+fn parse_integer(s: &str) -> miette::Result<usize> {
+    if let Some((pos, ch)) = s.char_indices().find(|(_, ch)| !ch.is_ascii_digit()) {
+        bail!("invalid character {ch} at position {pos}");
     }
-    Ok(draws)
+    s.parse()
+        .into_diagnostic()
+        .wrap_err("failed to parse as usize")
 }
 
-fn parse_draw<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> miette::Result<Option<ColorSet>> {
+fn parse_draws<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> miette::Result<Vec<ColorSet>> {
+    let mut draws = Vec::new();
     let mut set = ColorSet::default();
-    while let Some(token) = parse_color(tokens)? {
-        match token.color {
-            Color::Red => set.red += token.count,
-            Color::Green => set.green += token.count,
-            Color::Blue => set.blue += token.count,
-        }
-        if token.is_terminal {
-            break;
+    loop {
+        // every draw has at least one color, including after a separator
+        let token = parse_color(tokens)?.ok_or_else(|| miette!("expected color count"))?;
+        set.add(token.color, token.count)
+            .ok_or_else(|| miette!("too many {} cubes in one draw", token.color))?;
+        match token.separator {
+            Separator::Color => {}
+            Separator::Draw => draws.push(std::mem::take(&mut set)),
+            Separator::None => {
+                draws.push(set);
+                break;
+            }
         }
     }
-    if set.is_empty() {
-        Ok(None)
-    } else {
-        Ok(Some(set))
+    if let Some(token) = tokens.next() {
+        bail!("unexpected token {token}, expected , or ; before it");
     }
+    Ok(draws)
 }
 
-struct ColorRecord {
+struct ColorRecord<'a> {
     count: usize,
-    color: Color,
-    is_terminal: bool,
-}
-
-enum Color {
-    Red,
-    Green,
-    Blue,
+    color: &'a str,
+    separator: Separator,
 }
 
-impl FromStr for Color {
-    type Err = miette::Report;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "red" => Self::Red,
-            "green" => Self::Green,
-            "blue" => Self::Blue,
-            other => bail!("invalid color: {other}"),
-        })
-    }
+enum Separator {
+    Color,
+    Draw,
+    None,
 }
 
 fn parse_color<'a>(
     tokens: &mut impl Iterator<Item = &'a str>,
-) -> miette::Result<Option<ColorRecord>> {
+) -> miette::Result<Option<ColorRecord<'a>>> {
     let Some(count) = tokens.next() else {
         return Ok(None);
     };
-    let count = count.parse::<usize>().into_diagnostic()?;
+    let count = parse_integer(count)?;
     let color = tokens
         .next()
         .ok_or_else(|| miette!("expecting color name"))?;
-    let (color, is_terminal) = match color.chars().last() {
-        Some(',') => (&color[..color.len() - 1], false),
-        Some(';') => (&color[..color.len() - 1], true),
-        Some(_) => (color, true),
-        None => bail!("empty color name"),
+    let (color, separator) = if let Some(color) = color.strip_suffix(',') {
+        (color, Separator::Color)
+    } else if let Some(color) = color.strip_suffix(';') {
+        (color, Separator::Draw)
+    } else {
+        (color, Separator::None)
     };
-    let color = color.parse()?;
+    ensure!(!color.is_empty(), "empty color name");
+    ensure!(
+        color.chars().all(char::is_alphabetic),
+        "invalid color: {color}"
+    );
     Ok(Some(ColorRecord {
         count,
         color,
-        is_terminal,
+        separator,
     }))
 }
 
 #[cfg(test)]
 mod tests {
     use super::parse_game;
+    use crate::day2::Palette;
     use indoc::indoc;
 
     #[test]
@@ -150,7 +141,7 @@ mod tests {
         for line in INPUT.lines() {
             let game = parse_game(line).unwrap();
             let cover = game.cover();
-            powers.push(cover.power());
+            powers.push(cover.power(&Palette::rgb()));
         }
         assert_eq!(powers, [48, 12, 1560, 630, 36]);
     }
@@ -0,0 +1,348 @@
+use super::{ColorSet, Game, Palette};
+use crate::errors::{GenericError, ParseError};
+use miette::{Context, IntoDiagnostic};
+use std::{io::BufRead, iter, ops::Range};
+
+pub fn part1(input: impl BufRead, bag: &ColorSet) -> miette::Result<usize> {
+    let mut sum = 0;
+    for_each_game(input, |_, game| {
+        if game.is_possible(bag) {
+            sum += game.id;
+        }
+        Ok(())
+    })?;
+    Ok(sum)
+}
+
+/// Sums the powers without keeping the games around.
+///
+/// A colour first seen in a later game makes every earlier power zero, so
+/// the sum starts over whenever the palette grows.
+pub fn part2(input: impl BufRead) -> miette::Result<usize> {
+    let mut palette = Palette::default();
+    let mut sum = 0;
+    for_each_game(input, |_, game| {
+        for (color, _) in game.cover() {
+            if !palette.contains(color) {
+                palette.colors.insert(color.to_owned());
+                sum = 0;
+            }
+        }
+        // colours missing from the cover have no cubes, so the power is zero
+        if game.cover().count() == palette.colors.len() {
+            sum += game.cover().map(|(_, count)| count).product::<usize>();
+        }
+        Ok(())
+    })?;
+    Ok(sum)
+}
+
+/// Parses one game per line, accepting any colour unless a `palette` is given.
+pub fn parse_games(input: impl BufRead, palette: Option<&Palette>) -> miette::Result<Vec<Game>> {
+    let mut games = Vec::new();
+    for_each_game(input, |num, game| {
+        let game = game.to_game();
+        if let Some(palette) = palette {
+            palette
+                .check(&game)
+                .wrap_err_with(|| format!("at line {num}"))?;
+        }
+        games.push(game);
+        Ok(())
+    })?;
+    Ok(games)
+}
+
+/// Parses the input line by line into buffers reused from one line to the
+/// next, handing each game to `f` as a view into them.
+fn for_each_game(
+    mut input: impl BufRead,
+    mut f: impl FnMut(usize, GameRef) -> miette::Result<()>,
+) -> miette::Result<()> {
+    let mut buf = Vec::new();
+    let mut scratch = Scratch::default();
+    for num in 0.. {
+        buf.clear();
+        let read = input
+            .read_until(b'\n', &mut buf)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to read line {num}"))?;
+        if read == 0 {
+            break;
+        }
+        let line = trim_newline(&buf);
+        let game = parse_game(line, &mut scratch)
+            .map_err(|err| err.into_report(line))
+            .wrap_err_with(|| format!("at line {num}"))?;
+        f(num, game)?;
+    }
+    Ok(())
+}
+
+fn trim_newline(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+/// A parse error pointing at a byte range of the line.
+#[derive(Debug)]
+struct SpanError {
+    message: &'static str,
+    span: Range<usize>,
+}
+
+impl SpanError {
+    fn into_report(self, line: &[u8]) -> miette::Report {
+        let source = String::from_utf8_lossy(line);
+        let error = GenericError::new(self.message, self.span);
+        ParseError::new(source, iter::once(error)).into()
+    }
+}
+
+/// Cube counts found while validating a line, as byte ranges into it so
+/// that nothing is copied out.
+#[derive(Debug, Default)]
+struct Scratch {
+    // the total of each colour in each draw, draw after draw
+    cubes: Vec<(Range<usize>, usize)>,
+    // where each draw ends in `cubes`
+    draw_ends: Vec<usize>,
+    // the largest total of each colour over the draws so far
+    cover: Vec<(Range<usize>, usize)>,
+}
+
+impl Scratch {
+    fn clear(&mut self) {
+        self.cubes.clear();
+        self.draw_ends.clear();
+        self.cover.clear();
+    }
+
+    /// Adds `count` cubes of the colour at `color` to the current draw, or
+    /// returns `None` if its total overflows.
+    ///
+    /// Colours are looked up by scanning the draw and the cover, which only
+    /// hold one entry per colour.
+    fn add(&mut self, line: &[u8], color: Range<usize>, count: usize) -> Option<()> {
+        let same =
+            |(other, _): &&mut (Range<usize>, usize)| line[other.clone()] == line[color.clone()];
+        let start = self.draw_ends.last().copied().unwrap_or_default();
+        let total = match self.cubes[start..].iter_mut().find(same) {
+            Some((_, total)) => {
+                *total = total.checked_add(count)?;
+                *total
+            }
+            None => {
+                self.cubes.push((color.clone(), count));
+                count
+            }
+        };
+        // colours without cubes are left out, as in `ColorSet`
+        if total > 0 {
+            match self.cover.iter_mut().find(same) {
+                Some((_, max)) => *max = (*max).max(total),
+                None => self.cover.push((color, total)),
+            }
+        }
+        Some(())
+    }
+
+    fn end_draw(&mut self) {
+        self.draw_ends.push(self.cubes.len());
+    }
+}
+
+/// A game that has been validated but not copied out of the line.
+struct GameRef<'a> {
+    id: usize,
+    line: &'a str,
+    scratch: &'a Scratch,
+}
+
+impl<'a> GameRef<'a> {
+    /// The smallest bag that makes the game possible, one colour at a time.
+    fn cover(&self) -> impl Iterator<Item = (&'a str, usize)> + '_ {
+        self.scratch
+            .cover
+            .iter()
+            .map(|(color, count)| (&self.line[color.clone()], *count))
+    }
+
+    fn is_possible(&self, bag: &ColorSet) -> bool {
+        self.cover().all(|(color, count)| count <= bag.get(color))
+    }
+
+    fn to_game(&self) -> Game {
+        let mut start = 0;
+        let draws = self
+            .scratch
+            .draw_ends
+            .iter()
+            .map(|&end| {
+                let cubes = &self.scratch.cubes[start..end];
+                start = end;
+                ColorSet::from_counts(
+                    cubes
+                        .iter()
+                        .map(|(color, count)| (&self.line[color.clone()], *count)),
+                )
+            })
+            .collect();
+        Game { id: self.id, draws }
+    }
+}
+
+struct Cursor<'a> {
+    line: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.line.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn separator(&mut self) -> Result<(), SpanError> {
+        let start = self.pos;
+        self.skip_whitespace();
+        if self.pos == start {
+            return Err(self.error("expected whitespace", start));
+        }
+        Ok(())
+    }
+
+    fn error(&self, message: &'static str, start: usize) -> SpanError {
+        // point at the offending byte when nothing has been consumed yet
+        let end = if start == self.pos {
+            (self.pos + 1).min(self.line.len())
+        } else {
+            self.pos
+        };
+        SpanError {
+            message,
+            span: start..end,
+        }
+    }
+
+    fn expect(&mut self, token: &[u8], message: &'static str) -> Result<(), SpanError> {
+        if self.line[self.pos..].starts_with(token) {
+            self.pos += token.len();
+            Ok(())
+        } else {
+            Err(self.error(message, self.pos))
+        }
+    }
+
+    fn integer(&mut self) -> Result<usize, SpanError> {
+        let start = self.pos;
+        let mut value: usize = 0;
+        while let Some(digit) = self.peek().filter(u8::is_ascii_digit) {
+            value = value
+                .checked_mul(10)
+                .and_then(|value| value.checked_add((digit - b'0') as usize))
+                .ok_or_else(|| self.error("number is too large", start))?;
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(self.error("expected a number", start));
+        }
+        Ok(value)
+    }
+
+    fn color(&mut self) -> Result<Range<usize>, SpanError> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|byte| !byte.is_ascii_whitespace() && byte != b',' && byte != b';')
+        {
+            self.pos += 1;
+        }
+        let is_color = std::str::from_utf8(&self.line[start..self.pos])
+            .is_ok_and(|color| !color.is_empty() && color.chars().all(char::is_alphabetic));
+        if is_color {
+            Ok(start..self.pos)
+        } else {
+            Err(self.error("expected a color name", start))
+        }
+    }
+}
+
+fn parse_game<'a>(line: &'a [u8], scratch: &'a mut Scratch) -> Result<GameRef<'a>, SpanError> {
+    scratch.clear();
+    let mut cursor = Cursor { line, pos: 0 };
+    cursor.skip_whitespace();
+    cursor.expect(b"Game", "expected Game")?;
+    cursor.separator()?;
+    let id = cursor.integer()?;
+    cursor.expect(b":", "expected :")?;
+    cursor.separator()?;
+    loop {
+        let start = cursor.pos;
+        let count = cursor.integer()?;
+        cursor.separator()?;
+        let color = cursor.color()?;
+        scratch
+            .add(line, color, count)
+            .ok_or_else(|| cursor.error("too many cubes of this color", start))?;
+        match cursor.peek() {
+            Some(b',') => {
+                cursor.pos += 1;
+                cursor.separator()?;
+            }
+            Some(b';') => {
+                scratch.end_draw();
+                cursor.pos += 1;
+                cursor.separator()?;
+            }
+            None => break,
+            Some(byte) if byte.is_ascii_whitespace() => {
+                cursor.skip_whitespace();
+                if cursor.peek().is_none() {
+                    break;
+                }
+                return Err(cursor.error("expected , or ; before this", cursor.pos));
+            }
+            Some(_) => return Err(cursor.error("expected , or ;", cursor.pos)),
+        }
+    }
+    scratch.end_draw();
+    // everything but the colours is ASCII, and those were checked
+    let line = std::str::from_utf8(line).expect("validated as UTF-8 above");
+    Ok(GameRef { id, line, scratch })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_game, Scratch};
+    use crate::day2::{ColorSet, Game};
+
+    #[test]
+    fn can_parse() {
+        let s = b"Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green";
+        let game = parse_game(s, &mut Scratch::default()).unwrap().to_game();
+        let expected = Game {
+            id: 1,
+            draws: vec![
+                ColorSet::new(4, 0, 3),
+                ColorSet::new(1, 2, 6),
+                ColorSet::new(0, 2, 0),
+            ],
+        };
+        assert_eq!(game, expected);
+    }
+
+    #[test]
+    fn error_span() {
+        let err = parse_game(b"Game 1: 3 blue, x red", &mut Scratch::default())
+            .err()
+            .unwrap();
+        assert_eq!(err.message, "expected a number");
+        assert_eq!(err.span, 16..17);
+    }
+}
@@ -1,196 +1,394 @@
+mod edit;
+mod render;
+
+pub use render::Highlight;
+
+use crate::{
+    errors::{GenericError, ParseError},
+    grid::{Grid, Neighbourhood},
+    parse::lines_with_offsets,
+};
 use miette::{Context, IntoDiagnostic};
-use std::io::{BufRead, BufReader, Read};
+use std::{
+    io::Read,
+    ops::{RangeBounds, RangeInclusive},
+};
 
 pub fn part1(input: impl Read) -> miette::Result<usize> {
-    let grid = build_grid(input)?;
-    let sum = grid
-        .numbers
-        .iter()
-        .filter_map(|num| grid.has_adjacent_symbol(&num.span).then_some(num.value))
-        .sum();
-    Ok(sum)
+    let schematic = Schematic::parse(input)?;
+    Ok(schematic.part_number_sum())
 }
 
 pub fn part2(input: impl Read) -> miette::Result<usize> {
-    let grid = build_grid(input)?;
-    let sum = grid
-        .iter()
-        .filter_map(|(row, col, _)| grid.gear_ratio(row, col))
-        .sum();
-    Ok(sum)
-}
-
-fn build_grid(input: impl Read) -> miette::Result<Grid> {
-    let buf_reader = BufReader::new(input);
-    let mut grid = Grid::default();
-    for (row, line) in buf_reader.lines().enumerate() {
-        let line = line
-            .into_diagnostic()
-            .wrap_err_with(|| format!("failed to read line {row}"))?;
+    let schematic = Schematic::parse(input)?;
+    Ok(schematic.gear_ratio_sum())
+}
+
+fn build_schematic(mut input: impl Read) -> miette::Result<Schematic> {
+    let mut source = String::new();
+    input
+        .read_to_string(&mut source)
+        .into_diagnostic()
+        .wrap_err("failed to read input")?;
+    let mut rows = Vec::new();
+    let mut numbers = Vec::new();
+    let mut symbols = Vec::new();
+    let mut errors = Vec::new();
+    let mut width = None;
+    for (offset, line) in lines_with_offsets(&source) {
         if line.is_empty() {
             continue;
         }
-        let mut cells = Vec::with_capacity(line.len());
-        let mut maybe_start = None;
-        for (col, ch) in line.chars().enumerate() {
+        let row = rows.len();
+        // columns count characters, so keep the byte offset of each around for slicing
+        let chars: Vec<_> = line.char_indices().collect();
+        let mut cells = Vec::with_capacity(chars.len());
+        let mut col = 0;
+        while let Some(&(idx, ch)) = chars.get(col) {
             if ch.is_ascii_digit() {
+                let start = col;
+                while chars.get(col).is_some_and(|(_, ch)| ch.is_ascii_digit()) {
+                    col += 1;
+                }
+                // digits are ASCII, so the number takes up one byte per column
+                let digits = &line[idx..idx + (col - start)];
                 // Number cells have the index where the number will be in the numbers vector
-                cells.push(GridCell::Number(grid.numbers.len()));
-            } else if ch != '.' {
-                cells.push(GridCell::Symbol(ch));
-            } else {
-                cells.push(GridCell::Empty);
-            }
-            if let Some(start) = maybe_start {
-                if !ch.is_ascii_digit() {
-                    let end = col;
-                    let slice = &line[start..end];
-                    let value: usize = slice
-                        .parse()
-                        .into_diagnostic()
-                        .wrap_err("could not parse sequence as usize at line {row} col {col}")?;
-                    grid.numbers.push(Number {
+                cells.extend((start..col).map(|_| GridCell::Number(numbers.len())));
+                match digits.parse() {
+                    Ok(value) => numbers.push(Number {
                         value,
-                        span: Span { row, start, end },
-                    });
-                    maybe_start = None;
+                        span: Span {
+                            row,
+                            start,
+                            end: col,
+                        },
+                    }),
+                    Err(_) => {
+                        let span = offset + idx..offset + idx + digits.len();
+                        errors.push(GenericError::new("number is too large", span));
+                    }
                 }
-            } else if ch.is_ascii_digit() {
-                maybe_start = Some(col);
+                continue;
+            }
+            if ch != '.' {
+                // Likewise for symbol cells
+                cells.push(GridCell::Symbol(symbols.len()));
+                symbols.push(Symbol { ch, row, col });
+            } else {
+                cells.push(GridCell::Empty);
             }
+            col += 1;
         }
-        if let Some(start) = maybe_start {
-            let slice = &line[start..];
-            let value: usize = slice
-                .parse()
-                .into_diagnostic()
-                .wrap_err("could not parse sequence as usize at line {row} col {col}")?;
-            grid.numbers.push(Number {
-                value,
-                span: Span {
-                    row,
-                    start,
-                    end: line.len(),
-                },
-            });
+        let width = *width.get_or_insert(cells.len());
+        if cells.len() != width {
+            let message = format!("row {row} has {} cells, expected {width}", cells.len());
+            errors.push(GenericError::new(message, offset..offset + line.len()));
         }
-        grid.cells.push(cells);
+        rows.push(cells);
+    }
+    if !errors.is_empty() {
+        return Err(ParseError::new(source, errors.into_iter()).into());
     }
-    Ok(grid)
+    let grid = Grid::from_rows(rows)?;
+    Ok(Schematic::new(grid, numbers, symbols))
 }
 
 #[derive(Debug, Clone)]
 enum GridCell {
     Number(usize),
-    Symbol(char),
+    Symbol(usize),
     Empty,
 }
 
 impl GridCell {
-    fn is_symbol(&self) -> bool {
-        matches!(self, Self::Symbol(_))
+    fn number(&self) -> Option<usize> {
+        match self {
+            GridCell::Number(idx) => Some(*idx),
+            _ => None,
+        }
+    }
+
+    fn symbol(&self) -> Option<usize> {
+        match self {
+            GridCell::Symbol(idx) => Some(*idx),
+            _ => None,
+        }
     }
 }
 
-#[derive(Debug)]
-struct Number {
-    value: usize,
-    span: Span,
+/// A number in the schematic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Number {
+    pub value: usize,
+    pub span: Span,
 }
 
-#[derive(Debug)]
-struct Span {
-    row: usize,
-    start: usize,
-    end: usize,
+/// The cells a number occupies, from column `start` up to `end` exclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub row: usize,
+    pub start: usize,
+    pub end: usize,
 }
 
-#[derive(Default, Debug)]
-struct Grid {
-    cells: Vec<Vec<GridCell>>,
-    numbers: Vec<Number>,
+impl Span {
+    fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.start..self.end).map(|col| (self.row, col))
+    }
 }
 
-impl Grid {
-    fn width(&self) -> usize {
-        if !self.cells.is_empty() {
-            self.cells[0].len()
-        } else {
-            0
+/// A symbol in the schematic, that is, anything other than a digit or `.`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub ch: char,
+    pub row: usize,
+    pub col: usize,
+}
+
+/// Decides which symbols are gears and how their ratio is worked out.
+#[derive(Debug, Clone)]
+pub struct GearRule {
+    symbols: Vec<char>,
+    neighbours: RangeInclusive<usize>,
+    combine: Combine,
+}
+
+impl GearRule {
+    /// Gears are any of `symbols` with a number of adjacent numbers within
+    /// `neighbours`, and their ratio combines those numbers with `combine`.
+    pub fn new(
+        symbols: impl IntoIterator<Item = char>,
+        neighbours: RangeInclusive<usize>,
+        combine: Combine,
+    ) -> Self {
+        Self {
+            symbols: symbols.into_iter().collect(),
+            neighbours,
+            combine,
         }
     }
-    fn height(&self) -> usize {
-        self.cells.len()
-    }
-    fn has_adjacent_symbol(&self, span: &Span) -> bool {
-        let (start, end) = (
-            span.start.saturating_sub(1),
-            (span.end + 1).min(self.width()),
-        );
-        let check_row = |row: usize| {
-            for col in start..end {
-                if self.cells[row][col].is_symbol() {
-                    return true;
-                }
+}
+
+impl Default for GearRule {
+    /// The rule from the puzzle: a `*` next to exactly two numbers, whose ratio is their product.
+    fn default() -> Self {
+        Self::new(['*'], 2..=2, Combine::Product)
+    }
+}
+
+/// How the numbers around a gear make up its ratio.
+#[derive(Debug, Clone, Copy)]
+pub enum Combine {
+    Product,
+    Sum,
+    Max,
+    Custom(fn(&[usize]) -> usize),
+}
+
+impl Combine {
+    fn apply(self, values: &[usize]) -> usize {
+        match self {
+            Combine::Product => values.iter().product(),
+            Combine::Sum => values.iter().sum(),
+            Combine::Max => values.iter().copied().max().unwrap_or_default(),
+            Combine::Custom(combine) => combine(values),
+        }
+    }
+}
+
+/// An engine schematic, with the adjacency between numbers and symbols
+/// worked out up front.
+///
+/// Numbers and symbols are referred to by their index, in reading order.
+#[derive(Debug)]
+pub struct Schematic {
+    grid: Grid<GridCell>,
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    // both sides of the bipartite graph between numbers and symbols
+    number_symbols: Vec<Vec<usize>>,
+    symbol_numbers: Vec<Vec<usize>>,
+    // kept up to date by edits
+    part_sum: usize,
+    gear_sum: usize,
+}
+
+impl Schematic {
+    pub fn parse(input: impl Read) -> miette::Result<Self> {
+        build_schematic(input)
+    }
+
+    fn new(grid: Grid<GridCell>, numbers: Vec<Number>, symbols: Vec<Symbol>) -> Self {
+        let number_symbols: Vec<_> = numbers
+            .iter()
+            .map(|num| adjacent(&grid, num.span.cells(), GridCell::symbol))
+            .collect();
+        let mut symbol_numbers = vec![Vec::new(); symbols.len()];
+        for (num_idx, adjacent) in number_symbols.iter().enumerate() {
+            for &sym_idx in adjacent {
+                symbol_numbers[sym_idx].push(num_idx);
             }
-            false
-        };
-        if self.cells[span.row][start].is_symbol() || self.cells[span.row][end - 1].is_symbol() {
-            return true;
         }
-        if span.row > 0 && check_row(span.row - 1) {
-            return true;
+        let mut schematic = Self {
+            grid,
+            numbers,
+            symbols,
+            number_symbols,
+            symbol_numbers,
+            part_sum: 0,
+            gear_sum: 0,
+        };
+        schematic.part_sum = (0..schematic.numbers.len())
+            .map(|idx| schematic.part_value(idx))
+            .sum();
+        schematic.gear_sum = (0..schematic.symbols.len())
+            .map(|idx| schematic.gear_value(idx))
+            .sum();
+        schematic
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.height()
+    }
+
+    pub fn numbers(&self) -> &[Number] {
+        &self.numbers
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    /// Index of the number occupying the given cell, if any.
+    pub fn number_at(&self, row: usize, col: usize) -> Option<usize> {
+        self.grid.get(row as isize, col as isize)?.number()
+    }
+
+    /// Index of the symbol in the given cell, if any.
+    pub fn symbol_at(&self, row: usize, col: usize) -> Option<usize> {
+        self.grid.get(row as isize, col as isize)?.symbol()
+    }
+
+    /// Indices of the symbols adjacent to the given number.
+    pub fn symbols_of(&self, number: usize) -> &[usize] {
+        &self.number_symbols[number]
+    }
+
+    /// Indices of the numbers adjacent to the given symbol.
+    pub fn numbers_of(&self, symbol: usize) -> &[usize] {
+        &self.symbol_numbers[symbol]
+    }
+
+    /// Numbers adjacent to at least one symbol.
+    pub fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers_touching(1..)
+    }
+
+    /// Sum of the part numbers, which is the answer to part 1.
+    pub fn part_number_sum(&self) -> usize {
+        self.part_sum
+    }
+
+    /// Sum of the ratios of the gears under the default rule, which is the
+    /// answer to part 2.
+    pub fn gear_ratio_sum(&self) -> usize {
+        self.gear_sum
+    }
+
+    /// Numbers adjacent to a number of symbols within `range`.
+    pub fn numbers_touching(
+        &self,
+        range: impl RangeBounds<usize>,
+    ) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(&self.number_symbols)
+            .filter(move |(_, symbols)| range.contains(&symbols.len()))
+            .map(|(num, _)| num)
+    }
+
+    /// Indices of the symbols adjacent to exactly `count` numbers.
+    pub fn symbols_with(&self, count: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..self.symbols.len()).filter(move |&idx| self.symbol_numbers[idx].len() == count)
+    }
+
+    /// Sum of the numbers adjacent to each symbol, by symbol index.
+    pub fn symbol_sums(&self) -> Vec<usize> {
+        self.symbol_numbers
+            .iter()
+            .map(|numbers| numbers.iter().map(|&idx| self.numbers[idx].value).sum())
+            .collect()
+    }
+
+    /// Gears, which are `*` symbols next to exactly two numbers, with their ratios.
+    pub fn gears(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.gears_with(GearRule::default())
+    }
+
+    /// Symbols that are gears according to `rule`, with their ratios.
+    pub fn gears_with(&self, rule: GearRule) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.symbols.len()).filter_map(move |idx| Some((idx, self.gear_ratio(idx, &rule)?)))
+    }
+
+    fn gear_ratio(&self, symbol: usize, rule: &GearRule) -> Option<usize> {
+        let numbers = &self.symbol_numbers[symbol];
+        if !rule.symbols.contains(&self.symbols[symbol].ch)
+            || !rule.neighbours.contains(&numbers.len())
+        {
+            return None;
         }
-        if span.row + 1 < self.height() && check_row(span.row + 1) {
-            return true;
+        let values: Vec<_> = numbers.iter().map(|&num| self.numbers[num].value).collect();
+        Some(rule.combine.apply(&values))
+    }
+
+    // what a number adds to the part number sum
+    fn part_value(&self, number: usize) -> usize {
+        if self.number_symbols[number].is_empty() {
+            0
+        } else {
+            self.numbers[number].value
         }
-        false
-    }
-    fn neighbours(&self, row: usize, col: usize) -> impl Iterator<Item = &GridCell> {
-        const DELTAS: [(i32, i32); 8] = [
-            (-1, -1),
-            (-1, 0),
-            (-1, 1),
-            (0, -1),
-            (0, 1),
-            (1, -1),
-            (1, 0),
-            (1, 1),
-        ];
-        DELTAS.iter().filter_map(move |(d_row, d_col)| {
-            let (new_row, new_col) = (row as i32 + d_row, col as i32 + d_col);
-            (new_row >= 0
-                && new_col >= 0
-                && (new_row as usize) < self.height()
-                && (new_col as usize) < self.width())
-            .then_some(&self.cells[new_row as usize][new_col as usize])
-        })
-    }
-    fn gear_ratio(&self, row: usize, col: usize) -> Option<usize> {
-        if !matches!(self.cells[row][col], GridCell::Symbol('*')) {
-            return None;
+    }
+
+    // what a symbol adds to the gear ratio sum
+    fn gear_value(&self, symbol: usize) -> usize {
+        self.gear_ratio(symbol, &GearRule::default())
+            .unwrap_or_default()
+    }
+
+    // the character in the given cell, as it was written
+    fn char_at(&self, row: usize, col: usize) -> char {
+        match self.grid[(row, col)] {
+            GridCell::Number(idx) => {
+                let num = &self.numbers[idx];
+                // pad back any leading zeros the number was written with
+                let width = num.span.end - num.span.start;
+                let digits = format!("{:0width$}", num.value);
+                digits.as_bytes()[col - num.span.start] as char
+            }
+            GridCell::Symbol(idx) => self.symbols[idx].ch,
+            GridCell::Empty => '.',
         }
-        let mut neighbour_numbers: Vec<_> = self
-            .neighbours(row, col)
-            .filter_map(|cell| match cell {
-                GridCell::Number(index) => Some(*index),
-                _ => None,
-            })
-            .collect();
-        neighbour_numbers.dedup(); // Numbers may occupy multiple cells
-        (neighbour_numbers.len() == 2).then(|| {
-            neighbour_numbers
-                .into_iter()
-                .map(|idx| self.numbers[idx].value)
-                .product()
-        })
-    }
-    fn iter(&self) -> impl Iterator<Item = (usize, usize, &GridCell)> {
-        self.cells.iter().enumerate().flat_map(|(row, row_cells)| {
-            row_cells
-                .iter()
-                .enumerate()
-                .map(move |(col, cell)| (row, col, cell))
-        })
     }
 }
+
+/// Indices picked by `pick` out of the cells around `cells`, sorted and
+/// without duplicates.
+fn adjacent(
+    grid: &Grid<GridCell>,
+    cells: impl IntoIterator<Item = (usize, usize)>,
+    pick: impl Fn(&GridCell) -> Option<usize>,
+) -> Vec<usize> {
+    let mut adjacent: Vec<_> = cells
+        .into_iter()
+        .flat_map(|(row, col)| grid.neighbours(row, col, Neighbourhood::Eight))
+        .filter_map(|(_, _, cell)| pick(cell))
+        .collect();
+    adjacent.sort_unstable();
+    adjacent.dedup();
+    adjacent
+}
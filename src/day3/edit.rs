@@ -0,0 +1,213 @@
+use super::{adjacent, GridCell, Number, Schematic, Span, Symbol};
+use crate::grid::Neighbourhood;
+use miette::{bail, ensure};
+
+impl Schematic {
+    /// Writes `ch` into the given cell, updating the numbers, symbols and
+    /// sums around it without rebuilding the rest of the schematic.
+    ///
+    /// Writing a digit may merge it with the numbers on either side, and
+    /// overwriting one may split its number in two. Removed numbers and
+    /// symbols have their index taken over by the last one, and new ones
+    /// are added at the end, so indices only stay valid until the next edit.
+    pub fn set(&mut self, row: usize, col: usize, ch: char) -> miette::Result<()> {
+        ensure!(
+            row < self.height() && col < self.width(),
+            "({row}, {col}) is out of bounds"
+        );
+        // the numbers merged or split by this edit, left to right
+        let neighbours = if ch.is_ascii_digit() {
+            vec![col.checked_sub(1), Some(col), Some(col + 1)]
+        } else {
+            vec![Some(col)]
+        };
+        let mut old: Vec<_> = neighbours
+            .into_iter()
+            .flatten()
+            .filter_map(|col| self.number_at(row, col))
+            .collect();
+        old.dedup();
+        let new = self.renumber(row, col, ch, &old)?;
+
+        let (numbers, symbols) = self.affected(row, col);
+        self.part_sum -= numbers
+            .iter()
+            .map(|&idx| self.part_value(idx))
+            .sum::<usize>();
+        self.gear_sum -= symbols
+            .iter()
+            .map(|&idx| self.gear_value(idx))
+            .sum::<usize>();
+
+        old.sort_unstable_by(|a, b| b.cmp(a));
+        for idx in old {
+            self.remove_number(idx);
+        }
+        if let Some(idx) = self.symbol_at(row, col) {
+            self.remove_symbol(idx);
+        }
+        if !ch.is_ascii_digit() && ch != '.' {
+            self.add_symbol(Symbol { ch, row, col });
+        }
+        for num in new {
+            self.add_number(num);
+        }
+
+        let (numbers, symbols) = self.affected(row, col);
+        self.part_sum += numbers
+            .iter()
+            .map(|&idx| self.part_value(idx))
+            .sum::<usize>();
+        self.gear_sum += symbols
+            .iter()
+            .map(|&idx| self.gear_value(idx))
+            .sum::<usize>();
+        Ok(())
+    }
+
+    /// Empties the given cell, as if it held a `.`.
+    pub fn clear(&mut self, row: usize, col: usize) -> miette::Result<()> {
+        self.set(row, col, '.')
+    }
+
+    // the numbers left in the stretch of the row covered by `old` and the
+    // edited cell, once `ch` is written into it
+    fn renumber(
+        &self,
+        row: usize,
+        col: usize,
+        ch: char,
+        old: &[usize],
+    ) -> miette::Result<Vec<Number>> {
+        let spans = old.iter().map(|&idx| &self.numbers[idx].span);
+        let start = spans.clone().map(|span| span.start).fold(col, usize::min);
+        let end = spans.map(|span| span.end).fold(col + 1, usize::max);
+        let chars: Vec<_> = (start..end)
+            .map(|c| if c == col { ch } else { self.char_at(row, c) })
+            .collect();
+        let mut numbers = Vec::new();
+        let mut idx = 0;
+        while idx < chars.len() {
+            let len = chars[idx..]
+                .iter()
+                .take_while(|ch| ch.is_ascii_digit())
+                .count();
+            if len > 0 {
+                let digits: String = chars[idx..idx + len].iter().collect();
+                let span = Span {
+                    row,
+                    start: start + idx,
+                    end: start + idx + len,
+                };
+                let Ok(value) = digits.parse() else {
+                    bail!(
+                        "number at row {row}, columns {}..{} would be too large",
+                        span.start,
+                        span.end
+                    );
+                };
+                numbers.push(Number { value, span });
+            }
+            idx += len.max(1);
+        }
+        Ok(numbers)
+    }
+
+    // everything whose contribution to the sums may change when the given
+    // cell is edited: the numbers and symbols around it, which includes any
+    // number merged or split by the edit, and the symbols next to those
+    // numbers
+    fn affected(&self, row: usize, col: usize) -> (Vec<usize>, Vec<usize>) {
+        let window = self
+            .grid
+            .neighbours(row, col, Neighbourhood::Eight)
+            .map(|(_, _, cell)| cell)
+            .chain([&self.grid[(row, col)]]);
+        let mut numbers = Vec::new();
+        let mut symbols = Vec::new();
+        for cell in window {
+            numbers.extend(cell.number());
+            symbols.extend(cell.symbol());
+        }
+        numbers.sort_unstable();
+        numbers.dedup();
+        for &idx in &numbers {
+            symbols.extend(&self.number_symbols[idx]);
+        }
+        symbols.sort_unstable();
+        symbols.dedup();
+        (numbers, symbols)
+    }
+
+    fn add_number(&mut self, num: Number) {
+        let idx = self.numbers.len();
+        for cell in num.span.cells() {
+            self.grid[cell] = GridCell::Number(idx);
+        }
+        let symbols = adjacent(&self.grid, num.span.cells(), GridCell::symbol);
+        for &sym in &symbols {
+            self.symbol_numbers[sym].push(idx);
+        }
+        self.numbers.push(num);
+        self.number_symbols.push(symbols);
+    }
+
+    fn add_symbol(&mut self, sym: Symbol) {
+        let idx = self.symbols.len();
+        let cell = (sym.row, sym.col);
+        self.grid[cell] = GridCell::Symbol(idx);
+        let numbers = adjacent(&self.grid, [cell], GridCell::number);
+        for &num in &numbers {
+            self.number_symbols[num].push(idx);
+        }
+        self.symbols.push(sym);
+        self.symbol_numbers.push(numbers);
+    }
+
+    fn remove_number(&mut self, idx: usize) {
+        for &sym in &self.number_symbols[idx] {
+            self.symbol_numbers[sym].retain(|&num| num != idx);
+        }
+        let num = self.numbers.swap_remove(idx);
+        self.number_symbols.swap_remove(idx);
+        for cell in num.span.cells() {
+            self.grid[cell] = GridCell::Empty;
+        }
+        // point everything at the number that took its place
+        let moved = self.numbers.len();
+        if idx < moved {
+            for cell in self.numbers[idx].span.cells() {
+                self.grid[cell] = GridCell::Number(idx);
+            }
+            for &sym in &self.number_symbols[idx] {
+                for num in &mut self.symbol_numbers[sym] {
+                    if *num == moved {
+                        *num = idx;
+                    }
+                }
+            }
+        }
+    }
+
+    fn remove_symbol(&mut self, idx: usize) {
+        for &num in &self.symbol_numbers[idx] {
+            self.number_symbols[num].retain(|&sym| sym != idx);
+        }
+        let sym = self.symbols.swap_remove(idx);
+        self.symbol_numbers.swap_remove(idx);
+        self.grid[(sym.row, sym.col)] = GridCell::Empty;
+        // point everything at the symbol that took its place
+        let moved = self.symbols.len();
+        if idx < moved {
+            let cell = (self.symbols[idx].row, self.symbols[idx].col);
+            self.grid[cell] = GridCell::Symbol(idx);
+            for &num in &self.symbol_numbers[idx] {
+                for sym in &mut self.number_symbols[num] {
+                    if *sym == moved {
+                        *sym = idx;
+                    }
+                }
+            }
+        }
+    }
+}
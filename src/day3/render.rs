@@ -0,0 +1,123 @@
+use super::{GearRule, GridCell, Schematic};
+use crate::grid::Grid;
+use std::fmt::Write;
+
+const RESET: &str = "\x1b[0m";
+
+/// What a cell of the schematic is, for rendering purposes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    /// A digit of a number next to at least one symbol.
+    PartNumber,
+    /// A digit of a number next to no symbol.
+    LooseNumber,
+    /// A symbol that is a gear under the rule being rendered.
+    Gear,
+    /// Any other symbol.
+    Symbol,
+    Empty,
+}
+
+impl Highlight {
+    fn ansi(self) -> &'static str {
+        match self {
+            Highlight::PartNumber => "\x1b[32m",
+            Highlight::LooseNumber => "\x1b[31m",
+            Highlight::Gear => "\x1b[1;33m",
+            Highlight::Symbol => "\x1b[36m",
+            Highlight::Empty => "\x1b[2m",
+        }
+    }
+
+    fn class(self) -> &'static str {
+        match self {
+            Highlight::PartNumber => "part",
+            Highlight::LooseNumber => "loose",
+            Highlight::Gear => "gear",
+            Highlight::Symbol => "symbol",
+            Highlight::Empty => "empty",
+        }
+    }
+}
+
+impl Schematic {
+    /// How every cell is highlighted, with gears picked out by `rule`.
+    pub fn highlights(&self, rule: GearRule) -> Grid<Highlight> {
+        let mut gears = vec![false; self.symbols.len()];
+        for (idx, _) in self.gears_with(rule) {
+            gears[idx] = true;
+        }
+        self.grid.map(|cell| match *cell {
+            GridCell::Number(idx) if self.number_symbols[idx].is_empty() => Highlight::LooseNumber,
+            GridCell::Number(_) => Highlight::PartNumber,
+            GridCell::Symbol(idx) if gears[idx] => Highlight::Gear,
+            GridCell::Symbol(_) => Highlight::Symbol,
+            GridCell::Empty => Highlight::Empty,
+        })
+    }
+
+    /// Renders the schematic for a terminal, colouring part numbers green,
+    /// loose numbers red, gears bold yellow and other symbols cyan.
+    pub fn to_ansi(&self, rule: GearRule) -> String {
+        let mut out = String::new();
+        for row in self.runs(rule) {
+            for (highlight, text) in row {
+                write!(out, "{}{text}{RESET}", highlight.ansi()).unwrap();
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Renders the schematic as a standalone HTML page, with one CSS class
+    /// per kind of cell.
+    pub fn to_html(&self, rule: GearRule) -> String {
+        let mut out = String::from(concat!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<style>\n",
+            "pre { background: #111; color: #ccc; }\n",
+            ".part { color: #4c4; }\n",
+            ".loose { color: #e44; }\n",
+            ".gear { color: #ee4; font-weight: bold; }\n",
+            ".symbol { color: #4cc; }\n",
+            ".empty { color: #555; }\n",
+            "</style>\n</head>\n<body>\n<pre>\n",
+        ));
+        for row in self.runs(rule) {
+            for (highlight, text) in row {
+                write!(out, "<span class=\"{}\">", highlight.class()).unwrap();
+                for ch in text.chars() {
+                    match ch {
+                        '<' => out.push_str("&lt;"),
+                        '>' => out.push_str("&gt;"),
+                        '&' => out.push_str("&amp;"),
+                        _ => out.push(ch),
+                    }
+                }
+                out.push_str("</span>");
+            }
+            out.push('\n');
+        }
+        out.push_str("</pre>\n</body>\n</html>\n");
+        out
+    }
+
+    // each row as runs of consecutive cells with the same highlight
+    fn runs(&self, rule: GearRule) -> Vec<Vec<(Highlight, String)>> {
+        let highlights = self.highlights(rule);
+        highlights
+            .rows()
+            .enumerate()
+            .map(|(row, highlights)| {
+                let mut runs: Vec<(Highlight, String)> = Vec::new();
+                for (col, &highlight) in highlights.iter().enumerate() {
+                    let ch = self.char_at(row, col);
+                    match runs.last_mut() {
+                        Some((last, text)) if *last == highlight => text.push(ch),
+                        _ => runs.push((highlight, ch.to_string())),
+                    }
+                }
+                runs
+            })
+            .collect()
+    }
+}
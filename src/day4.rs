@@ -1,68 +1,319 @@
+mod rules;
+mod trace;
+
+pub use rules::{Scoring, Winnings};
+pub use trace::Trace;
+
 use crate::parse::parse;
-use chumsky::prelude::*;
-use miette::Context;
-use std::collections::{HashMap, HashSet};
+use chumsky::{input::Emitter, prelude::*};
+use miette::miette;
+use std::collections::{HashSet, VecDeque};
 
 pub fn part1(input: &str) -> miette::Result<usize> {
-    let mut sum = 0;
-    for (num, line) in input.lines().enumerate() {
-        let card = parse(line, card()).wrap_err_with(|| format!("at line {num}"))?;
-        sum += card.points();
+    let mut sum: usize = 0;
+    for card in parse_cards(input)? {
+        sum = card
+            .points(&Scoring::Doubling)
+            .and_then(|points| sum.checked_add(points))
+            .ok_or_else(|| miette!("points overflowed at card {}", card.id))?;
+    }
+    Ok(sum)
+}
+
+pub fn part2(input: &str) -> miette::Result<u128> {
+    let mut sum: u128 = 0;
+    for (id, count) in copies(input)? {
+        sum = sum
+            .checked_add(count)
+            .ok_or_else(|| miette!("total copy count overflowed at card {id}"))?;
     }
     Ok(sum)
 }
 
-pub fn part2(input: &str) -> miette::Result<usize> {
-    let mut counts: HashMap<_, usize> = HashMap::new();
-    for (num, line) in input.lines().enumerate() {
-        let card = parse(line, card()).wrap_err_with(|| format!("at line {num}"))?;
-        // the count of copies won by past cards
-        let count = counts.entry(card.id).or_default();
-        *count += 1; // count the original card
-        let count = *count; // drop the borrow
-        let matches = card.matches();
-        for id in card.id + 1..=card.id + matches {
-            *counts.entry(id).or_default() += count;
+/// Total number of copies of each card, including the original, along with its id.
+pub fn copies(input: &str) -> miette::Result<Vec<(usize, u128)>> {
+    copies_with(&parse(input, cards(true))?, Winnings::Next)
+}
+
+/// Total number of copies of each card when cards win according to `winnings`.
+pub fn copies_with(cards: &[Card], winnings: Winnings) -> miette::Result<Vec<(usize, u128)>> {
+    let mut cascade = Cascade::with_winnings(winnings, cards.len());
+    cards
+        .iter()
+        .map(|card| {
+            let count = cascade
+                .push(card.matches())
+                .ok_or_else(|| miette!("copy count overflowed at card {}", card.id))?;
+            Ok((card.id, count))
+        })
+        .collect()
+}
+
+/// Parses a table of cards, checking that it is well-formed.
+///
+/// Which cards a card wins depends on the rules, so a card that would win
+/// cards past the end of the table under the puzzle's rules is let through.
+pub fn parse_cards(input: &str) -> miette::Result<Vec<Card>> {
+    parse(input, cards(false))
+}
+
+/// The copy cascade, fed one card at a time in table order.
+///
+/// Only copies won for upcoming cards are kept, so memory is bounded by
+/// how far ahead a card can win rather than by the number of cards.
+#[derive(Debug, Clone)]
+pub struct Cascade<'w> {
+    winnings: Winnings<'w>,
+    // how many cards are left to push, so that wins past the end of the
+    // table are dropped rather than kept
+    remaining: usize,
+    // copies won so far for the cards after the last one pushed, nearest first
+    pending: VecDeque<u128>,
+}
+
+impl Default for Cascade<'_> {
+    fn default() -> Self {
+        Self::with_winnings(Winnings::Next, usize::MAX)
+    }
+}
+
+impl<'w> Cascade<'w> {
+    /// The puzzle's cascade, over a table of any length.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cascade over a table of `cards` cards that win according to
+    /// `winnings`.
+    pub fn with_winnings(winnings: Winnings<'w>, cards: usize) -> Self {
+        Self {
+            winnings,
+            remaining: cards,
+            pending: VecDeque::new(),
         }
     }
-    Ok(counts.into_values().sum())
+
+    /// Adds the next card, which has `matches` winning numbers, and returns
+    /// how many copies of it there are, or `None` if a count overflows.
+    pub fn push(&mut self, matches: usize) -> Option<u128> {
+        self.push_with(matches, |_, _| {})
+    }
+
+    // like `push`, but calls `won` with how far down the table each win
+    // lands and how many copies it adds, once the counts are known not to
+    // overflow, with zero standing for the card itself
+    fn push_with(&mut self, matches: usize, mut won: impl FnMut(usize, u128)) -> Option<u128> {
+        let count = self
+            .pending
+            .pop_front()
+            .unwrap_or_default()
+            .checked_add(1)?;
+        self.remaining = self.remaining.saturating_sub(1);
+        let mut total = count;
+        for offset in self.winnings.offsets(matches) {
+            if offset > self.remaining {
+                continue;
+            }
+            if offset == 0 {
+                // copies of the card itself don't win anything more
+                total = total.checked_add(count)?;
+            } else {
+                if self.pending.len() < offset {
+                    self.pending.resize(offset, 0);
+                }
+                let pending = &mut self.pending[offset - 1];
+                *pending = pending.checked_add(count)?;
+            }
+            won(offset, count);
+        }
+        Some(total)
+    }
 }
 
-#[derive(Default, Debug)]
-struct Card {
+/// A scratchcard, with its winning numbers and the numbers scratched off.
+#[derive(Debug)]
+pub struct Card {
     id: usize,
-    winning: HashSet<usize>,
-    scratched: HashSet<usize>,
+    winning: Numbers,
+    scratched: Numbers,
 }
 
 impl Card {
-    fn points(&self) -> usize {
-        let matches = self.matches();
-        if matches == 0 {
-            0
-        } else {
-            2_usize.pow((matches - 1) as u32)
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// How many of the scratched numbers are winning numbers.
+    pub fn matches(&self) -> usize {
+        self.winning.common(&self.scratched)
+    }
+
+    /// Points the card is worth, or `None` if they don't fit in a `usize`.
+    pub fn points(&self, scoring: &Scoring) -> Option<usize> {
+        scoring.points(self.matches())
+    }
+}
+
+/// A set of numbers, kept as a bitset when they are all small enough.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Numbers {
+    Bits(u128),
+    Set(HashSet<usize>),
+}
+
+impl Numbers {
+    fn contains(&self, num: usize) -> bool {
+        match self {
+            Numbers::Bits(bits) => num < 128 && bits & (1 << num) != 0,
+            Numbers::Set(set) => set.contains(&num),
         }
     }
-    fn matches(&self) -> usize {
-        self.winning.intersection(&self.scratched).count()
+
+    // how many numbers are in both sets
+    fn common(&self, other: &Numbers) -> usize {
+        match (self, other) {
+            (Numbers::Bits(a), Numbers::Bits(b)) => (a & b).count_ones() as usize,
+            (Numbers::Set(a), Numbers::Set(b)) => a.intersection(b).count(),
+            (Numbers::Set(set), bits) | (bits, Numbers::Set(set)) => {
+                set.iter().filter(|&&num| bits.contains(num)).count()
+            }
+        }
     }
 }
 
-fn card<'a>() -> impl Parser<'a, &'a str, Card, extra::Err<Rich<'a, char>>> {
-    let head = just("Card").padded();
+impl FromIterator<usize> for Numbers {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut bits = 0_u128;
+        let mut iter = iter.into_iter();
+        while let Some(num) = iter.next() {
+            if num >= 128 {
+                // too large for the bitset, so move everything over to a set
+                let mut set: HashSet<_> = (0..128).filter(|&n| bits & (1 << n) != 0).collect();
+                set.insert(num);
+                set.extend(iter);
+                return Numbers::Set(set);
+            }
+            bits |= 1 << num;
+        }
+        Numbers::Bits(bits)
+    }
+}
+
+type Spanned = (usize, SimpleSpan);
+
+/// A card as written, with the spans needed to point at problems.
+struct RawCard {
+    span: SimpleSpan,
+    id: Spanned,
+    winning: Vec<Spanned>,
+    scratched: Vec<Spanned>,
+}
 
-    let integer = text::int(10).from_str::<usize>().unwrapped().padded();
+impl RawCard {
+    fn to_card(&self) -> Card {
+        let numbers = |list: &[Spanned]| list.iter().map(|&(num, _)| num).collect();
+        Card {
+            id: self.id.0,
+            winning: numbers(&self.winning),
+            scratched: numbers(&self.scratched),
+        }
+    }
+}
+
+/// Checks that the table is well-formed, reporting every problem found.
+///
+/// With `within`, cards must also win no more cards than follow them.
+fn check(raw: Vec<RawCard>, within: bool, emitter: &mut Emitter<Rich<'_, char>>) -> Vec<Card> {
+    let mut ids = HashSet::new();
+    let mut cards = Vec::with_capacity(raw.len());
+    for (idx, card) in raw.iter().enumerate() {
+        let (id, span) = card.id;
+        if !ids.insert(id) {
+            emitter.emit(Rich::custom(
+                span,
+                format!("card {id} appears more than once"),
+            ));
+        } else if id != idx + 1 {
+            let message = format!("expected card {}, found card {id}", idx + 1);
+            emitter.emit(Rich::custom(span, message));
+        }
+
+        for list in [&card.winning, &card.scratched] {
+            let mut numbers = HashSet::new();
+            for &(num, span) in list {
+                if !numbers.insert(num) {
+                    emitter.emit(Rich::custom(span, format!("number {num} is repeated")));
+                }
+            }
+        }
+
+        // every card should look like the first one
+        let first = &raw[0];
+        let lists = [
+            ("winning", &card.winning, first.winning.len()),
+            ("scratched", &card.scratched, first.scratched.len()),
+        ];
+        for (name, list, expected) in lists {
+            if list.len() != expected {
+                let span = match (list.first(), list.last()) {
+                    (Some(first), Some(last)) => SimpleSpan::from(first.1.start..last.1.end),
+                    _ => card.span,
+                };
+                let message = format!(
+                    "card {id} has {} {name} numbers, expected {expected}",
+                    list.len()
+                );
+                emitter.emit(Rich::custom(span, message));
+            }
+        }
+
+        let parsed = card.to_card();
+        let following = raw.len() - idx - 1;
+        let matches = parsed.matches();
+        if within && matches > following {
+            let message = format!("card {id} wins {matches} cards, but only {following} follow it");
+            emitter.emit(Rich::custom(card.span, message));
+        }
+        cards.push(parsed);
+    }
+    cards
+}
+
+fn cards<'a>(within: bool) -> impl Parser<'a, &'a str, Vec<Card>, extra::Err<Rich<'a, char>>> {
+    card()
+        .padded()
+        .repeated()
+        .collect::<Vec<_>>()
+        .then_ignore(end())
+        .validate(move |raw, _, emitter| check(raw, within, emitter))
+}
+
+fn card<'a>() -> impl Parser<'a, &'a str, RawCard, extra::Err<Rich<'a, char>>> {
+    let integer = text::digits(10)
+        .to_slice()
+        .try_map(|digits: &str, span| {
+            digits
+                .parse::<usize>()
+                .map_err(|err| Rich::custom(span, err))
+        })
+        .map_with(|num, extra| (num, extra.span()));
 
-    let id = integer.then_ignore(just(':')).padded();
+    let id = integer
+        .padded_by(text::inline_whitespace())
+        .then_ignore(just(':'));
 
-    let number_sequence = integer.padded().repeated().collect::<HashSet<_>>();
+    let number_sequence = integer
+        .padded_by(text::inline_whitespace())
+        .repeated()
+        .collect::<Vec<_>>();
 
-    head.ignore_then(id)
+    just("Card")
+        .ignore_then(id)
         .then(number_sequence)
         .then_ignore(just('|'))
         .then(number_sequence)
-        .map(|((id, winning), scratched)| Card {
+        .map_with(|((id, winning), scratched), extra| RawCard {
+            span: extra.span(),
             id,
             winning,
             scratched,
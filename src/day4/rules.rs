@@ -0,0 +1,68 @@
+/// How many points a card is worth for its number of matches.
+pub enum Scoring<'s> {
+    /// One point for the first match, doubled for every match after it, as
+    /// in the puzzle.
+    Doubling,
+    /// One point per match.
+    Linear,
+    /// Points follow the Fibonacci sequence from one match onwards: 1, 2, 3,
+    /// 5, 8 and so on.
+    Fibonacci,
+    /// Whatever the function gives for the number of matches.
+    Custom(&'s dyn Fn(usize) -> usize),
+}
+
+impl Scoring<'_> {
+    /// Points for `matches` matches, or `None` if they don't fit in a `usize`.
+    pub fn points(&self, matches: usize) -> Option<usize> {
+        match self {
+            Self::Doubling if matches == 0 => Some(0),
+            Self::Doubling => 2_usize.checked_pow((matches - 1).try_into().ok()?),
+            Self::Linear => Some(matches),
+            Self::Fibonacci if matches == 0 => Some(0),
+            Self::Fibonacci => {
+                let (mut prev, mut points) = (1_usize, 1);
+                for _ in 1..matches {
+                    (prev, points) = (points, prev.checked_add(points)?);
+                }
+                Some(points)
+            }
+            Self::Custom(points) => Some(points(matches)),
+        }
+    }
+}
+
+/// Which cards a card wins copies of, for its number of matches.
+///
+/// Every copy of a card wins the same cards as the original. Wins that
+/// fall past the end of the table are dropped.
+#[derive(Debug, Default, Clone, Copy)]
+pub enum Winnings<'w> {
+    /// One copy of each of the next `n` cards for `n` matches, as in the
+    /// puzzle.
+    #[default]
+    Next,
+    /// The `k`th match wins a copy of the card `offsets[k]` places further
+    /// down the table. Matches beyond the end of the list win nothing, and
+    /// neither does an offset of zero.
+    Offsets(&'w [usize]),
+    /// Each match wins another copy of the card itself. Those copies don't
+    /// win anything more, or there would be no end to it.
+    Itself,
+}
+
+impl<'w> Winnings<'w> {
+    /// How far down the table each copy of a card with `matches` matches
+    /// wins a card, where zero stands for the card itself.
+    pub(super) fn offsets(self, matches: usize) -> impl Iterator<Item = usize> + 'w {
+        // how many cards ahead are won, which are listed, and how many copies of itself
+        let (next, listed, itself): (_, &[usize], _) = match self {
+            Self::Next => (matches, &[], 0),
+            Self::Offsets(offsets) => (0, &offsets[..matches.min(offsets.len())], 0),
+            Self::Itself => (0, &[], matches),
+        };
+        (1..=next)
+            .chain(listed.iter().copied().filter(|&offset| offset > 0))
+            .chain((0..itself).map(|_| 0))
+    }
+}
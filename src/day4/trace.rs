@@ -0,0 +1,124 @@
+use super::{Card, Cascade, Winnings};
+use miette::miette;
+use std::{collections::HashMap, fmt::Write};
+
+/// Where the copies of every card came from.
+///
+/// Cards are looked up by id, and each source is the id of a card that won
+/// copies of it along with how many copies it won.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    ids: Vec<usize>,
+    // where each id is in `ids`
+    index: HashMap<usize, usize>,
+    copies: Vec<u128>,
+    sources: Vec<Vec<(usize, u128)>>,
+}
+
+impl Trace {
+    /// Runs the cascade over `cards` when they win according to `winnings`,
+    /// keeping track of every win.
+    pub fn new(cards: &[Card], winnings: Winnings) -> miette::Result<Self> {
+        let mut cascade = Cascade::with_winnings(winnings, cards.len());
+        let mut copies = Vec::with_capacity(cards.len());
+        // by index rather than id while the cascade runs
+        let mut sources: Vec<Vec<(usize, u128)>> = vec![Vec::new(); cards.len()];
+        for (idx, card) in cards.iter().enumerate() {
+            let count = cascade
+                .push_with(card.matches(), |offset, won| {
+                    let Some(target) = sources.get_mut(idx + offset) else {
+                        return;
+                    };
+                    // a card may be won more than once by the same card, and the
+                    // cascade has already checked that the total fits
+                    match target.last_mut() {
+                        Some((source, total)) if *source == idx => *total += won,
+                        _ => target.push((idx, won)),
+                    }
+                })
+                .ok_or_else(|| miette!("copy count overflowed at card {}", card.id))?;
+            copies.push(count);
+        }
+        let ids: Vec<_> = cards.iter().map(Card::id).collect();
+        let sources = sources
+            .into_iter()
+            .map(|sources| {
+                let by_id = sources.into_iter().map(|(idx, won)| (ids[idx], won));
+                by_id.collect()
+            })
+            .collect();
+        let index = ids.iter().enumerate().map(|(idx, &id)| (id, idx)).collect();
+        Ok(Self {
+            ids,
+            index,
+            copies,
+            sources,
+        })
+    }
+
+    /// Ids of the cards, in table order.
+    pub fn ids(&self) -> &[usize] {
+        &self.ids
+    }
+
+    /// Total number of copies of the card, including the original.
+    pub fn copies(&self, id: usize) -> Option<u128> {
+        self.index(id).map(|idx| self.copies[idx])
+    }
+
+    /// The cards that won copies of the card, with how many copies each won,
+    /// in table order.
+    pub fn sources(&self, id: usize) -> Option<&[(usize, u128)]> {
+        self.index(id).map(|idx| self.sources[idx].as_slice())
+    }
+
+    /// Renders the trace as JSON.
+    ///
+    /// Each card has its total number of copies and the copies won by
+    /// every source card, keyed by id.
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\n  \"cards\": [");
+        for (idx, id) in self.ids.iter().enumerate() {
+            let sep = if idx == 0 { "" } else { "," };
+            let sources: Vec<_> = self.sources[idx]
+                .iter()
+                .map(|(source, won)| format!("\"{source}\": {won}"))
+                .collect();
+            let sources = if sources.is_empty() {
+                "{}".to_owned()
+            } else {
+                format!("{{ {} }}", sources.join(", "))
+            };
+            write!(
+                out,
+                "{sep}\n    {{ \"id\": {id}, \"copies\": {}, \"sources\": {sources} }}",
+                self.copies[idx],
+            )
+            .unwrap();
+        }
+        out.push_str("\n  ]\n}\n");
+        out
+    }
+
+    /// Renders the trace in Graphviz DOT format, with an edge from each card
+    /// to every card it won copies of, labelled with how many.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph cascade {\n");
+        for (idx, id) in self.ids.iter().enumerate() {
+            let copies = self.copies[idx];
+            let plural = if copies == 1 { "copy" } else { "copies" };
+            writeln!(out, "    {id} [label=\"Card {id}\\n{copies} {plural}\"];").unwrap();
+        }
+        for (idx, id) in self.ids.iter().enumerate() {
+            for (source, won) in &self.sources[idx] {
+                writeln!(out, "    {source} -> {id} [label=\"{won}\"];").unwrap();
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn index(&self, id: usize) -> Option<usize> {
+        self.index.get(&id).copied()
+    }
+}
@@ -91,7 +91,7 @@ impl FromStr for Hand {
         for i in 0..5 {
             data[i].write(s[i..i + 1].parse()?);
         }
-        let cards = unsafe { std::mem::transmute::<_, [Card; 5]>(data) };
+        let cards = unsafe { std::mem::transmute::<[MaybeUninit<Card>; 5], [Card; 5]>(data) };
         Ok(Self { cards })
     }
 }
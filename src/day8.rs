@@ -1,26 +1,34 @@
+mod export;
+mod matcher;
+
+pub use matcher::Matcher;
+
 use crate::parse::parse;
-use chumsky::prelude::*;
-use miette::miette;
-use std::collections::HashMap;
+use chumsky::{input::Emitter, prelude::*};
+use miette::{miette, Diagnostic, LabeledSpan, SourceCode};
+use std::collections::{hash_map::Entry, HashMap};
+use thiserror::Error;
 
 pub fn part1(input: &str) -> miette::Result<usize> {
-    let maze = parse(input, maze())?;
-    steps(&maze, "AAA")
+    let maze = Maze::parse(input)?;
+    let walks = maze.walk_all(&Matcher::Label("AAA"), &Matcher::Label("ZZZ"))?;
+    let walk = walks
+        .first()
+        .ok_or_else(|| miette!("node AAA does not exist"))?;
+    Ok(walk.steps)
 }
 
-pub fn part2(input: &str) -> miette::Result<usize> {
-    let maze = parse(input, maze())?;
-    let steps = maze
-        .starts()
-        .map(|node| steps(&maze, node))
-        .collect::<miette::Result<Vec<_>>>()?;
-    steps
-        .into_iter()
-        .reduce(lcm)
-        .ok_or_else(|| miette!("empty set"))
+pub fn part2(input: &str) -> miette::Result<u128> {
+    let maze = Maze::parse(input)?;
+    let walks = maze.walk_all(&Matcher::Suffix("A"), &Matcher::Suffix("Z"))?;
+    let mut steps = walks.into_iter().map(|walk| walk.steps as u128);
+    let first = steps.next().ok_or_else(|| miette!("empty set"))?;
+    steps.try_fold(first, |acc, count| {
+        lcm(acc, count).ok_or_else(|| miette!("step count overflowed: lcm({acc}, {count})"))
+    })
 }
 
-fn gcd(mut a: usize, mut b: usize) -> usize {
+fn gcd(mut a: u128, mut b: u128) -> u128 {
     while b != 0 {
         let t = b;
         b = a % b;
@@ -29,49 +37,213 @@ fn gcd(mut a: usize, mut b: usize) -> usize {
     a
 }
 
-fn lcm(a: usize, b: usize) -> usize {
-    (a * b) / gcd(a, b)
+// dividing first keeps intermediate values no larger than the result
+fn lcm(a: u128, b: u128) -> Option<u128> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b)
 }
 
-fn steps(maze: &Maze, start: &str) -> miette::Result<usize> {
-    let mut curr = start;
-    for (count, dir) in maze.instructions().enumerate() {
-        if curr.ends_with('Z') {
-            return Ok(count);
-        }
-        curr = maze.apply(curr, dir)?;
+/// Dense identifier of a node in a [`Maze`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    fn index(self) -> usize {
+        self.0 as usize
     }
-    Err(miette!("empty instructions"))
 }
 
-struct Maze<'a> {
-    nodes: HashMap<&'a str, Edges<'a>>,
+/// A network of nodes with interned labels.
+///
+/// Labels are mapped to dense [`NodeId`]s once at parse time, so walking the
+/// network is just indexing into a flat edge table.
+pub struct Maze<'a> {
+    source: &'a str,
+    labels: Vec<&'a str>,
+    spans: Vec<SimpleSpan>,
+    ids: HashMap<&'a str, NodeId>,
+    edges: Vec<Edges>,
     instr: Vec<Direction>,
 }
 
 impl<'a> Maze<'a> {
-    fn starts(&self) -> impl Iterator<Item = &'a str> + '_ {
-        self.nodes.keys().filter(|key| key.ends_with('A')).copied()
+    pub fn parse(input: &'a str) -> miette::Result<Self> {
+        parse(input, maze())
+    }
+
+    /// Number of nodes in the network.
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    pub fn id(&self, label: &str) -> Option<NodeId> {
+        self.ids.get(label).copied()
+    }
+
+    pub fn label(&self, node: NodeId) -> &'a str {
+        self.labels[node.index()]
+    }
+
+    /// Nodes selected by `matcher`, in order of first appearance.
+    pub fn starts<'m>(&'m self, matcher: &'m Matcher) -> impl Iterator<Item = NodeId> + 'm {
+        self.nodes()
+            .filter(move |&node| matcher.matches(self.label(node)))
+    }
+
+    /// Walks from `start` until reaching a node selected by `goal`.
+    pub fn walk(&self, start: NodeId, goal: &Matcher) -> miette::Result<Walk> {
+        self.walk_with(start, &self.select(goal))
+    }
+
+    /// Walks from every node selected by `starts` to the nearest node selected by `goal`.
+    pub fn walk_all(&self, starts: &Matcher, goal: &Matcher) -> miette::Result<Vec<Walk>> {
+        let goals = self.select(goal);
+        self.starts(starts)
+            .map(|start| self.walk_with(start, &goals))
+            .collect()
+    }
+
+    // evaluates the matcher once per node rather than once per step
+    fn select(&self, matcher: &Matcher) -> Vec<bool> {
+        self.labels
+            .iter()
+            .map(|label| matcher.matches(label))
+            .collect()
+    }
+
+    fn walk_with(&self, start: NodeId, goals: &[bool]) -> miette::Result<Walk> {
+        // nodes reached at the start of the instructions, as coming back to
+        // one of them there without reaching a goal means going around in
+        // circles
+        let mut seen = vec![false; self.len()];
+        let mut path = vec![start];
+        let mut curr = start;
+        for (steps, dir) in self.instructions().enumerate() {
+            let offset = steps % self.instr.len();
+            if goals[curr.index()] {
+                return Ok(Walk {
+                    steps,
+                    offset,
+                    path,
+                });
+            }
+            if offset == 0 {
+                if seen[curr.index()] {
+                    return Err(self.trapped(start).into());
+                }
+                seen[curr.index()] = true;
+            }
+            curr = self.apply(curr, dir);
+            path.push(curr);
+        }
+        Err(miette!("empty instructions"))
+    }
+
+    /// Walks from `start` until a `(node, instruction offset)` state repeats.
+    ///
+    /// Returns every state visited, in order, and the position at which the
+    /// repeating part begins.
+    fn cycle(&self, start: NodeId) -> (Vec<State>, usize) {
+        let mut seen = HashMap::new();
+        let mut states = Vec::new();
+        if self.instr.is_empty() {
+            return (states, 0);
+        }
+        let mut state = State {
+            node: start,
+            offset: 0,
+        };
+        while let Entry::Vacant(entry) = seen.entry(state) {
+            entry.insert(states.len());
+            states.push(state);
+            state = State {
+                node: self.apply(state.node, self.instr[state.offset]),
+                offset: (state.offset + 1) % self.instr.len(),
+            };
+        }
+        let cycle_start = seen[&state];
+        (states, cycle_start)
+    }
+
+    fn trapped(&self, start: NodeId) -> TrappedError {
+        let (states, cycle_start) = self.cycle(start);
+        let mut cycle: Vec<NodeId> = Vec::new();
+        for state in &states[cycle_start..] {
+            if !cycle.contains(&state.node) {
+                cycle.push(state.node);
+            }
+        }
+        let labels = cycle
+            .iter()
+            .enumerate()
+            .map(|(idx, node)| {
+                let text = (idx == 0).then(|| "cycle entered here".to_owned());
+                LabeledSpan::new_with_span(text, self.spans[node.index()].into_range())
+            })
+            .collect();
+        let names: Vec<_> = cycle.iter().map(|&node| self.label(node)).collect();
+        TrappedError {
+            start: self.label(start).to_owned(),
+            cycle: format!("{} -> {}", names.join(" -> "), names[0]),
+            source_code: self.source.to_owned(),
+            labels,
+        }
     }
+
+    fn nodes(&self) -> impl Iterator<Item = NodeId> {
+        (0..self.labels.len() as u32).map(NodeId)
+    }
+
     fn instructions(&self) -> impl Iterator<Item = Direction> + '_ {
         self.instr.iter().cycle().copied()
     }
-    fn apply(&self, node: &'a str, dir: Direction) -> miette::Result<&'a str> {
-        let edges = self
-            .nodes
-            .get(node)
-            .ok_or_else(|| miette!("node {node} does not exist"))?;
 
-        Ok(match dir {
+    fn apply(&self, node: NodeId, dir: Direction) -> NodeId {
+        let edges = &self.edges[node.index()];
+        match dir {
             Direction::Left => edges.left,
             Direction::Right => edges.right,
-        })
+        }
+    }
+}
+
+/// The outcome of walking a [`Maze`] from a start node to a goal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Walk {
+    /// Number of steps taken.
+    pub steps: usize,
+    /// Offset into the instructions at arrival.
+    pub offset: usize,
+    /// Every node visited, from the start to the goal inclusive.
+    pub path: Vec<NodeId>,
+}
+
+impl Walk {
+    pub fn start(&self) -> NodeId {
+        self.path[0]
+    }
+
+    pub fn end(&self) -> NodeId {
+        self.path[self.path.len() - 1]
     }
 }
 
-struct Edges<'a> {
-    left: &'a str,
-    right: &'a str,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct State {
+    node: NodeId,
+    offset: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Edges {
+    left: NodeId,
+    right: NodeId,
 }
 
 #[derive(Clone, Copy)]
@@ -80,14 +252,135 @@ enum Direction {
     Right,
 }
 
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Direction::Left => "L",
+            Direction::Right => "R",
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("no goal is reachable from {start}, trapped in cycle {cycle}")]
+struct TrappedError {
+    start: String,
+    cycle: String,
+    source_code: String,
+    labels: Vec<LabeledSpan>,
+}
+
+impl Diagnostic for TrappedError {
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source_code)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(self.labels.iter().cloned()))
+    }
+}
+
+type Spanned<'a> = (&'a str, SimpleSpan);
+type RawEdges<'a> = (Spanned<'a>, Spanned<'a>);
+
+#[derive(Default)]
+struct MazeBuilder<'a> {
+    labels: Vec<&'a str>,
+    ids: HashMap<&'a str, NodeId>,
+    edges: Vec<Option<Edges>>,
+    // where each node is defined, or first referenced if it never is
+    spans: Vec<SimpleSpan>,
+}
+
+impl<'a> MazeBuilder<'a> {
+    fn intern(&mut self, (label, span): Spanned<'a>) -> NodeId {
+        *self.ids.entry(label).or_insert_with(|| {
+            let id = NodeId(self.labels.len() as u32);
+            self.labels.push(label);
+            self.edges.push(None);
+            self.spans.push(span);
+            id
+        })
+    }
+
+    fn define(
+        &mut self,
+        node: Spanned<'a>,
+        left: Spanned<'a>,
+        right: Spanned<'a>,
+        emitter: &mut Emitter<Rich<'a, char>>,
+    ) {
+        let id = self.intern(node);
+        let edges = Edges {
+            left: self.intern(left),
+            right: self.intern(right),
+        };
+        let slot = &mut self.edges[id.index()];
+        if slot.is_none() {
+            self.spans[id.index()] = node.1;
+        } else {
+            emitter.emit(Rich::custom(
+                node.1,
+                format!("node {} is defined twice", node.0),
+            ));
+        }
+        *slot = Some(edges);
+    }
+
+    fn build(
+        self,
+        source: &'a str,
+        instr: Vec<Direction>,
+        emitter: &mut Emitter<Rich<'a, char>>,
+    ) -> Maze<'a> {
+        let edges = self
+            .edges
+            .into_iter()
+            .enumerate()
+            .map(|(idx, edges)| {
+                edges.unwrap_or_else(|| {
+                    let label = self.labels[idx];
+                    emitter.emit(Rich::custom(
+                        self.spans[idx],
+                        format!("node {label} is not defined"),
+                    ));
+                    // placeholder, the parse fails anyway
+                    let node = NodeId(idx as u32);
+                    Edges {
+                        left: node,
+                        right: node,
+                    }
+                })
+            })
+            .collect();
+        Maze {
+            source,
+            labels: self.labels,
+            spans: self.spans,
+            ids: self.ids,
+            edges,
+            instr,
+        }
+    }
+}
+
 fn maze<'a>() -> impl Parser<'a, &'a str, Maze<'a>, extra::Err<Rich<'a, char>>> {
-    let nodes = node().then_ignore(text::newline()).repeated().collect();
+    let nodes = node()
+        .then_ignore(text::newline())
+        .repeated()
+        .collect::<Vec<_>>();
 
     instructions()
         .then_ignore(text::whitespace())
         .then(nodes)
         .then_ignore(end())
-        .map(|(instr, nodes)| Maze { instr, nodes })
+        .validate(|(instr, nodes), extra, emitter| {
+            let mut builder = MazeBuilder::default();
+            for (node, (left, right)) in nodes {
+                builder.define(node, left, right, emitter);
+            }
+            builder.build(extra.slice(), instr, emitter)
+        })
 }
 
 fn instructions<'a>() -> impl Parser<'a, &'a str, Vec<Direction>, extra::Err<Rich<'a, char>>> {
@@ -99,23 +392,33 @@ fn instructions<'a>() -> impl Parser<'a, &'a str, Vec<Direction>, extra::Err<Ric
     .collect()
 }
 
-fn label<'a>() -> impl Parser<'a, &'a str, &'a str, extra::Err<Rich<'a, char>>> {
+fn label<'a>() -> impl Parser<'a, &'a str, Spanned<'a>, extra::Err<Rich<'a, char>>> {
     any()
         .filter(char::is_ascii_alphanumeric)
         .repeated()
-        .exactly(3)
+        .at_least(1)
         .to_slice()
+        .map_with(|label, extra| (label, extra.span()))
 }
 
-fn edges<'a>() -> impl Parser<'a, &'a str, Edges<'a>, extra::Err<Rich<'a, char>>> {
-    label()
-        .then_ignore(just(',').padded())
-        .then(label())
-        .map(|(left, right)| Edges { left, right })
+fn edges<'a>() -> impl Parser<'a, &'a str, RawEdges<'a>, extra::Err<Rich<'a, char>>> {
+    label().then_ignore(just(',').padded()).then(label())
 }
 
-fn node<'a>() -> impl Parser<'a, &'a str, (&'a str, Edges<'a>), extra::Err<Rich<'a, char>>> {
+fn node<'a>() -> impl Parser<'a, &'a str, (Spanned<'a>, RawEdges<'a>), extra::Err<Rich<'a, char>>> {
     label()
         .then_ignore(just('=').padded())
         .then(edges().delimited_by(just('('), just(')')))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::lcm;
+
+    #[test]
+    fn lcm_overflow() {
+        assert_eq!(lcm(4, 6), Some(12));
+        assert_eq!(lcm(u128::MAX, u128::MAX), Some(u128::MAX));
+        assert_eq!(lcm(u128::MAX, u128::MAX - 1), None);
+    }
+}
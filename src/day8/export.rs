@@ -0,0 +1,86 @@
+use super::{Direction, Matcher, Maze, NodeId};
+use std::fmt::Write;
+
+const START_COLOR: &str = "palegreen";
+const END_COLOR: &str = "lightcoral";
+const CYCLE_COLORS: [&str; 6] = [
+    "blue",
+    "darkorange",
+    "purple",
+    "forestgreen",
+    "red",
+    "brown",
+];
+
+impl<'a> Maze<'a> {
+    /// Renders the network in Graphviz DOT format.
+    ///
+    /// Nodes selected by `starts` and `ends` are filled in, and if
+    /// `cycles` is set, the edges each ghost keeps walking around after
+    /// leaving a start node are coloured, one colour per start.
+    pub fn to_dot(&self, starts: &Matcher, ends: &Matcher, cycles: bool) -> String {
+        // maps (node, direction) to the colour of the ghost cycle using that edge
+        let mut edge_colors = vec![[None; 2]; self.len()];
+        if cycles {
+            let colors = CYCLE_COLORS.iter().cycle();
+            for (start, color) in self.starts(starts).zip(colors) {
+                let (states, cycle_start) = self.cycle(start);
+                for state in &states[cycle_start..] {
+                    let dir = self.instr[state.offset];
+                    edge_colors[state.node.index()][dir as usize] = Some(*color);
+                }
+            }
+        }
+
+        let mut out = String::from("digraph maze {\n");
+        for node in self.nodes() {
+            let label = self.label(node);
+            if starts.matches(label) {
+                writeln!(
+                    out,
+                    "    {label:?} [style=filled, fillcolor={START_COLOR}];"
+                )
+                .unwrap();
+            } else if ends.matches(label) {
+                writeln!(out, "    {label:?} [style=filled, fillcolor={END_COLOR}];").unwrap();
+            }
+        }
+        for node in self.nodes() {
+            for dir in [Direction::Left, Direction::Right] {
+                let (from, to) = (self.label(node), self.label(self.apply(node, dir)));
+                write!(out, "    {from:?} -> {to:?} [label=\"{dir}\"").unwrap();
+                if let Some(color) = edge_colors[node.index()][dir as usize] {
+                    write!(out, ", color={color}, penwidth=2").unwrap();
+                }
+                out.push_str("];\n");
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Renders the network as JSON adjacency lists.
+    ///
+    /// Each node label maps to its `[left, right]` neighbours, and the
+    /// instructions are kept as the original `L`/`R` string.
+    pub fn to_json(&self) -> String {
+        let instr: String = self.instr.iter().map(ToString::to_string).collect();
+        let mut out = format!("{{\n  \"instructions\": \"{instr}\",\n  \"nodes\": {{");
+        for (idx, node) in self.nodes().enumerate() {
+            let sep = if idx == 0 { "" } else { "," };
+            // labels are ASCII alphanumeric, so they never need escaping
+            let [label, left, right] = self.neighbourhood(node);
+            write!(out, "{sep}\n    \"{label}\": [\"{left}\", \"{right}\"]").unwrap();
+        }
+        out.push_str("\n  }\n}\n");
+        out
+    }
+
+    fn neighbourhood(&self, node: NodeId) -> [&'a str; 3] {
+        [
+            self.label(node),
+            self.label(self.apply(node, Direction::Left)),
+            self.label(self.apply(node, Direction::Right)),
+        ]
+    }
+}
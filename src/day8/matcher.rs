@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+
+/// Selects nodes of a [`Maze`](super::Maze) by label.
+pub enum Matcher<'m> {
+    /// Exactly this label.
+    Label(&'m str),
+    /// Any label ending with this suffix.
+    Suffix(&'m str),
+    /// A glob-like pattern, where `?` matches any single character and `*`
+    /// matches any (possibly empty) sequence of characters.
+    Pattern(&'m str),
+    /// Any of these labels.
+    Set(HashSet<&'m str>),
+    /// Whatever the predicate accepts.
+    Predicate(&'m dyn Fn(&str) -> bool),
+}
+
+impl Matcher<'_> {
+    pub fn matches(&self, label: &str) -> bool {
+        match self {
+            Self::Label(expected) => label == *expected,
+            Self::Suffix(suffix) => label.ends_with(suffix),
+            Self::Pattern(pattern) => glob(pattern.as_bytes(), label.as_bytes()),
+            Self::Set(labels) => labels.contains(label),
+            Self::Predicate(predicate) => predicate(label),
+        }
+    }
+}
+
+// labels are ASCII, so matching byte-by-byte is the same as char-by-char
+fn glob(pattern: &[u8], label: &[u8]) -> bool {
+    let (mut p, mut l) = (0, 0);
+    // where to resume if the current attempt after a `*` fails
+    let mut backtrack = None;
+    while l < label.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, l));
+                p += 1;
+            }
+            Some(&ch) if ch == b'?' || ch == label[l] => {
+                p += 1;
+                l += 1;
+            }
+            _ => match backtrack {
+                // let the last `*` swallow one more character
+                Some((star, from)) => {
+                    backtrack = Some((star, from + 1));
+                    p = star + 1;
+                    l = from + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&ch| ch == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob;
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob(b"??A", b"11A"));
+        assert!(!glob(b"??A", b"1A"));
+        assert!(glob(b"*Z", b"Z"));
+        assert!(glob(b"*Z", b"XYZ"));
+        assert!(!glob(b"*Z", b"ZZA"));
+        assert!(glob(b"A*B*C", b"AxxBxBxC"));
+        assert!(glob(b"*", b""));
+        assert!(!glob(b"A", b""));
+    }
+}
@@ -18,6 +18,24 @@ impl GenericError {
     }
 }
 
+#[derive(Debug, Error, Diagnostic)]
+#[error("{message}")]
+#[diagnostic(severity(Warning))]
+pub(crate) struct GenericWarning {
+    message: String,
+    #[label]
+    label: SourceSpan,
+}
+
+impl GenericWarning {
+    pub(crate) fn new(message: impl Into<String>, label: impl Into<SourceSpan>) -> Self {
+        Self {
+            message: message.into(),
+            label: label.into(),
+        }
+    }
+}
+
 #[derive(Debug, Error, Diagnostic)]
 #[error("failed to parse input")]
 pub(crate) struct ParseError {
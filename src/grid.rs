@@ -0,0 +1,290 @@
+use miette::ensure;
+use std::{
+    collections::VecDeque,
+    ops::{Index, IndexMut},
+};
+
+/// Which cells count as adjacent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// Up, down, left and right.
+    Four,
+    /// Also the diagonals.
+    Eight,
+}
+
+impl Neighbourhood {
+    fn deltas(self) -> &'static [(isize, isize)] {
+        const DELTAS: [(isize, isize); 8] = [
+            (-1, 0),
+            (0, -1),
+            (0, 1),
+            (1, 0),
+            (-1, -1),
+            (-1, 1),
+            (1, -1),
+            (1, 1),
+        ];
+        match self {
+            Neighbourhood::Four => &DELTAS[..4],
+            Neighbourhood::Eight => &DELTAS,
+        }
+    }
+}
+
+/// A rectangular grid of cells, stored row by row.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Builds a grid from its rows, which must all have the same length.
+    pub fn from_rows(rows: impl IntoIterator<Item = Vec<T>>) -> miette::Result<Self> {
+        let mut grid = Self {
+            width: 0,
+            height: 0,
+            cells: Vec::new(),
+        };
+        for row in rows {
+            if grid.height == 0 {
+                grid.width = row.len();
+            }
+            ensure!(
+                row.len() == grid.width,
+                "row {} has {} cells, expected {}",
+                grid.height,
+                row.len(),
+                grid.width
+            );
+            grid.cells.extend(row);
+            grid.height += 1;
+        }
+        Ok(grid)
+    }
+
+    /// Parses a character map, one row per non-empty line.
+    pub fn parse(input: &str, mut cell: impl FnMut(char) -> T) -> miette::Result<Self> {
+        let rows = input
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.chars().map(&mut cell).collect());
+        Self::from_rows(rows)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The cell at the given coordinates, or `None` if they are out of bounds.
+    pub fn get(&self, row: isize, col: isize) -> Option<&T> {
+        self.index_of(row, col).map(|idx| &self.cells[idx])
+    }
+
+    pub fn get_mut(&mut self, row: isize, col: isize) -> Option<&mut T> {
+        self.index_of(row, col).map(|idx| &mut self.cells[idx])
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks panics on zero, but a zero-width grid has no cells anyway
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// Cells of the given column, top to bottom.
+    ///
+    /// Panics if the column is out of bounds, like [`Grid::row`].
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> {
+        assert!(col < self.width, "column {col} is out of bounds");
+        self.cells.iter().skip(col).step_by(self.width.max(1))
+    }
+
+    /// Cells going down and to the right from the given position.
+    pub fn diagonal(&self, row: usize, col: usize) -> impl Iterator<Item = &T> {
+        self.walk(row, col, (1, 1))
+    }
+
+    /// Cells going down and to the left from the given position.
+    pub fn anti_diagonal(&self, row: usize, col: usize) -> impl Iterator<Item = &T> {
+        self.walk(row, col, (1, -1))
+    }
+
+    /// Every cell with its coordinates, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &T)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(idx, cell)| (idx / self.width, idx % self.width, cell))
+    }
+
+    /// A grid of the same shape with `f` applied to every cell.
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Cells adjacent to the given position, with their coordinates.
+    pub fn neighbours(
+        &self,
+        row: usize,
+        col: usize,
+        neighbourhood: Neighbourhood,
+    ) -> impl Iterator<Item = (usize, usize, &T)> {
+        neighbourhood
+            .deltas()
+            .iter()
+            .filter_map(move |(d_row, d_col)| {
+                let (row, col) = (row as isize + d_row, col as isize + d_col);
+                let cell = self.get(row, col)?;
+                Some((row as usize, col as usize, cell))
+            })
+    }
+
+    /// Positions of the connected region around the given position where
+    /// `belongs` holds, in breadth-first order.
+    ///
+    /// The region is empty if `belongs` doesn't hold for the starting cell.
+    pub fn region(
+        &self,
+        row: usize,
+        col: usize,
+        neighbourhood: Neighbourhood,
+        belongs: impl Fn(&T) -> bool,
+    ) -> Vec<(usize, usize)> {
+        let mut region = Vec::new();
+        if !belongs(&self[(row, col)]) {
+            return region;
+        }
+        let mut visited = vec![false; self.cells.len()];
+        let mut queue = VecDeque::from([(row, col)]);
+        visited[row * self.width + col] = true;
+        while let Some((row, col)) = queue.pop_front() {
+            region.push((row, col));
+            for (row, col, cell) in self.neighbours(row, col, neighbourhood) {
+                let idx = row * self.width + col;
+                if !visited[idx] && belongs(cell) {
+                    visited[idx] = true;
+                    queue.push_back((row, col));
+                }
+            }
+        }
+        region
+    }
+
+    fn index_of(&self, row: isize, col: isize) -> Option<usize> {
+        let (row, col) = (usize::try_from(row).ok()?, usize::try_from(col).ok()?);
+        (row < self.height && col < self.width).then(|| row * self.width + col)
+    }
+
+    fn walk(
+        &self,
+        row: usize,
+        col: usize,
+        (d_row, d_col): (isize, isize),
+    ) -> impl Iterator<Item = &T> {
+        (0..).map_while(move |step| {
+            self.get(row as isize + step * d_row, col as isize + step * d_col)
+        })
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        self.get(row as isize, col as isize)
+            .unwrap_or_else(|| panic!("({row}, {col}) is out of bounds"))
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        self.get_mut(row as isize, col as isize)
+            .unwrap_or_else(|| panic!("({row}, {col}) is out of bounds"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grid, Neighbourhood};
+
+    const MAP: &str = "ab.\n.cd\ne..\n";
+
+    #[test]
+    fn views() {
+        let grid = Grid::parse(MAP, |ch| ch).unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid.row(1), ['.', 'c', 'd']);
+        assert_eq!(grid.column(0).collect::<String>(), "a.e");
+        assert_eq!(grid.diagonal(0, 0).collect::<String>(), "ac.");
+        assert_eq!(grid.anti_diagonal(0, 2).collect::<String>(), ".ce");
+        assert_eq!(grid.get(-1, 0), None);
+        assert_eq!(grid.get(2, 0), Some(&'e'));
+        assert_eq!(grid.get(0, 3), None);
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = Grid::parse(MAP, |ch| ch).unwrap();
+        let four: String = grid
+            .neighbours(0, 0, Neighbourhood::Four)
+            .map(|(_, _, ch)| ch)
+            .collect();
+        assert_eq!(four, "b.");
+        let eight: String = grid
+            .neighbours(1, 1, Neighbourhood::Eight)
+            .map(|(_, _, ch)| ch)
+            .collect();
+        assert_eq!(eight, "b.d.a.e.");
+    }
+
+    #[test]
+    fn region() {
+        let grid = Grid::parse(MAP, |ch| ch).unwrap();
+        let letter = |ch: &char| ch.is_alphabetic();
+        assert_eq!(
+            grid.region(0, 0, Neighbourhood::Four, letter),
+            [(0, 0), (0, 1), (1, 1), (1, 2)]
+        );
+        assert_eq!(
+            grid.region(0, 0, Neighbourhood::Eight, letter),
+            [(0, 0), (0, 1), (1, 1), (1, 2), (2, 0)]
+        );
+        assert!(grid.region(0, 2, Neighbourhood::Four, letter).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "column 4 is out of bounds")]
+    fn column_out_of_bounds() {
+        let grid = Grid::parse("abc\ndef\nghi\n", |ch| ch).unwrap();
+        let _ = grid.column(4);
+    }
+
+    #[test]
+    fn ragged() {
+        assert!(Grid::parse("ab\nc\n", |ch| ch).is_err());
+    }
+}
@@ -7,4 +7,5 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 mod errors;
+pub mod grid;
 mod parse;
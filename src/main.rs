@@ -0,0 +1,46 @@
+use aoc2023::{
+    day1::{self, Vocabulary},
+    day8::{Matcher, Maze},
+};
+use miette::{bail, Context, IntoDiagnostic, Report};
+use std::{env, fs};
+
+const USAGE: &str = "usage: aoc2023 day1 breakdown <input> | aoc2023 day8 (dot | json) <input>";
+
+fn main() -> miette::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args[..] {
+        ["day1", "breakdown", path] => show_breakdown(path),
+        ["day8", format, path] => export_maze(format, path),
+        _ => bail!(USAGE),
+    }
+}
+
+fn read(path: &str) -> miette::Result<String> {
+    fs::read_to_string(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to read {path}"))
+}
+
+// lines without digits are shown as warnings rather than stopping the report
+fn show_breakdown(path: &str) -> miette::Result<()> {
+    let input = read(path)?;
+    let breakdown = day1::breakdown_lenient(input.as_bytes(), &Vocabulary::english())?;
+    println!("{:?}", Report::new(breakdown));
+    Ok(())
+}
+
+// ghosts start on nodes ending in A and stop on nodes ending in Z, as in
+// part 2, and the cycles they end up in are coloured
+fn export_maze(format: &str, path: &str) -> miette::Result<()> {
+    let input = read(path)?;
+    let maze = Maze::parse(&input)?;
+    let out = match format {
+        "dot" => maze.to_dot(&Matcher::Suffix("A"), &Matcher::Suffix("Z"), true),
+        "json" => maze.to_json(),
+        _ => bail!(USAGE),
+    };
+    print!("{out}");
+    Ok(())
+}
@@ -15,3 +15,14 @@ pub(crate) fn parse<'a, T>(
     })?;
     Ok(output)
 }
+
+/// Splits `input` into lines like [`str::lines`], along with the byte offset of each.
+pub(crate) fn lines_with_offsets(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        Some((start, line))
+    })
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use aoc2023::day8::{self, Matcher};
+use std::process::{Command, Output};
+
+const DAY1_PATH: &str = "./tests/resources/day1/input.txt";
+const DAY8_PATH: &str = "./tests/resources/day8/input.txt";
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aoc2023"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn calibration_breakdown() {
+    let output = run(&["day1", "breakdown", DAY1_PATH]);
+    assert!(output.status.success());
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.contains("calibration values sum to 54019"));
+    assert!(report.contains("first: "));
+}
+
+#[test]
+fn export_maze() {
+    let input = common::read_string("day8/input.txt").unwrap();
+    let maze = day8::Maze::parse(&input).unwrap();
+
+    let output = run(&["day8", "json", DAY8_PATH]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), maze.to_json());
+
+    let output = run(&["day8", "dot", DAY8_PATH]);
+    assert!(output.status.success());
+    let dot = maze.to_dot(&Matcher::Suffix("A"), &Matcher::Suffix("Z"), true);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), dot);
+}
+
+#[test]
+fn usage() {
+    for args in [
+        &[][..],
+        &["day8", "svg", DAY8_PATH],
+        &["day1", "breakdown"],
+        &["day9"],
+    ] {
+        let output = run(args);
+        assert!(!output.status.success(), "{args:?}");
+        assert!(String::from_utf8(output.stderr).unwrap().contains("usage:"));
+    }
+}
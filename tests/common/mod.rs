@@ -11,3 +11,24 @@ pub fn read_string(path: &str) -> std::io::Result<String> {
     let path = Path::new("./tests/resources/").join(path);
     std::fs::read_to_string(path)
 }
+
+/// A small xorshift generator, so failures are reproducible from the seed.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// One of `items`, picked uniformly.
+    pub fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+}
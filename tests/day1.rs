@@ -53,3 +53,84 @@ fn part2_input() {
     let result = day1::part2(input).unwrap();
     assert_eq!(result, 54019);
 }
+
+#[test]
+fn custom_vocabulary() {
+    let vocabulary =
+        day1::Vocabulary::new([("zero", 0), ("I", 1), ("II", 2), ("III", 3), ("IV", 4)]).unwrap();
+    // the longest token wins at a given position, but the last token is
+    // whichever starts last, so "II" at the end reads as a 1
+    let input = "xIVzeroII\nzerothree0\n";
+    let result = day1::calibrate(input.as_bytes(), &vocabulary).unwrap();
+    assert_eq!(result, 41);
+}
+
+#[test]
+fn invalid_vocabulary() {
+    assert!(day1::Vocabulary::new([("", 1)]).is_err());
+    assert!(day1::Vocabulary::new([("ten", 10)]).is_err());
+    assert!(day1::Vocabulary::new([("uno", 1), ("uno", 2)]).is_err());
+    assert!(day1::Vocabulary::new([("uno", 1), ("uno", 1)]).is_ok());
+}
+
+#[test]
+fn breakdown_spans() {
+    let input = "xtwone3four\nzoneight\n7pqrst\n";
+    let breakdown = day1::breakdown(input.as_bytes(), &day1::Vocabulary::english()).unwrap();
+    let lines: Vec<_> = breakdown
+        .lines()
+        .iter()
+        .map(|line| {
+            (
+                line.first.span.clone(),
+                line.last.span.clone(),
+                line.value(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            (1..4, 7..11, 24),
+            (13..16, 15..20, 18),
+            (21..22, 21..22, 77)
+        ]
+    );
+    assert_eq!(&input[15..20], "eight");
+    assert_eq!(breakdown.sum(), 119);
+}
+
+#[test]
+fn all_invalid_lines() {
+    let input = "1abc2\nnothing\n3\nnope\n";
+    let err = day1::part1(input.as_bytes()).unwrap_err();
+    let related: Vec<_> = err.related().unwrap().map(|e| e.to_string()).collect();
+    assert_eq!(
+        related,
+        ["no valid digits in line 1", "no valid digits in line 3"]
+    );
+    // the report shows the offending lines even though the input was streamed
+    let report = format!("{err:?}");
+    assert!(report.contains("nothing") && report.contains("nope"));
+
+    let breakdown = day1::breakdown_lenient(input.as_bytes(), &day1::Vocabulary::digits()).unwrap();
+    assert!(breakdown.has_warnings());
+    assert_eq!(breakdown.sum(), 45);
+}
+
+#[test]
+fn part2_overlaps() {
+    // overlapping words at either end of a line count as both digits
+    let lines = [
+        ("oneight", 18),
+        ("eightwo", 82),
+        ("oneight5", 15),
+        ("5eightwo", 52),
+        ("oneight5eightwo", 12),
+        ("eightwo7oneight", 88),
+        ("twoneightwo", 22),
+    ];
+    for (line, value) in lines {
+        assert_eq!(day1::part2(line.as_bytes()).unwrap(), value, "{line}");
+    }
+}
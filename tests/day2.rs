@@ -83,3 +83,320 @@ mod chumsky {
         assert_eq!(result, 70950);
     }
 }
+
+mod streaming {
+    use super::SAMPLE;
+    use crate::common;
+    use aoc2023::day2::{
+        self,
+        streaming::{part1, part2},
+        ColorSet,
+    };
+    use indoc::indoc;
+
+    #[test]
+    fn part1_sample() {
+        let bag = ColorSet::new(12, 13, 14);
+        let result = part1(SAMPLE.as_bytes(), &bag).unwrap();
+        assert_eq!(result, 8);
+    }
+
+    #[test]
+    fn part1_input() {
+        let input = common::read_string("day2/input.txt").unwrap();
+        let bag = ColorSet::new(12, 13, 14);
+        let result = part1(input.as_bytes(), &bag).unwrap();
+        assert_eq!(result, 2076);
+    }
+
+    #[test]
+    fn part2_sample() {
+        let result = part2(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(result, 2286);
+    }
+
+    #[test]
+    fn part2_input() {
+        let input = common::read_string("day2/input.txt").unwrap();
+        let result = part2(input.as_bytes()).unwrap();
+        assert_eq!(result, 70950);
+    }
+
+    #[test]
+    fn repeated_and_late_colors() {
+        // red is drawn twice in one draw, and cyan only turns up in game 3
+        let input = indoc! { r#"
+		Game 1: 2 red, 3 red; 1 blue
+		Game 2: 0 cyan, 4 red, 2 blue
+		Game 3: 1 red, 2 blue, 3 cyan
+		Game 4: 2 red; 2 blue, 1 cyan
+	"# };
+        let bag = ColorSet::from_counts([("red", 4), ("blue", 2), ("cyan", 3)]);
+        assert_eq!(part1(input.as_bytes(), &bag).unwrap(), 9);
+        assert_eq!(part2(input.as_bytes()).unwrap(), 10);
+        assert_eq!(day2::part2(input).unwrap(), 10);
+    }
+}
+
+mod colors {
+    use aoc2023::day2::{self, ColorSet, Palette};
+
+    const INPUT: &str =
+        "Game 1: 2 cyan, 1 magenta; 3 yellow\nGame 2: 4 cyan; 1 magenta, 1 yellow\n";
+
+    #[test]
+    fn discovered_colors() {
+        let games = day2::parse_games(INPUT, None).unwrap();
+        let palette = Palette::discover(&games);
+        assert_eq!(palette, Palette::new(["cyan", "magenta", "yellow"]));
+
+        let bag = ColorSet::from_counts([("cyan", 3), ("magenta", 1), ("yellow", 3)]);
+        let possible: Vec<_> = games.iter().map(|game| game.is_possible(&bag)).collect();
+        assert_eq!(possible, [true, false]);
+
+        let powers: Vec<_> = games
+            .iter()
+            .map(|game| game.cover().power(&palette))
+            .collect();
+        assert_eq!(powers, [6, 4]);
+    }
+
+    #[test]
+    fn declared_colors() {
+        assert!(day2::parse_games(INPUT, Some(&Palette::rgb())).is_err());
+        assert!(day2::manual::parse_games(INPUT, Some(&Palette::rgb())).is_err());
+        let palette = Palette::new(["cyan", "magenta", "yellow"]);
+        assert_eq!(
+            day2::parse_games(INPUT, Some(&palette)).unwrap(),
+            day2::manual::parse_games(INPUT, Some(&palette)).unwrap()
+        );
+    }
+    #[test]
+    fn repeated_color_overflow() {
+        // each count fits, but the draw's total doesn't
+        let line = &format!("Game 1: {} red, 1 red", usize::MAX);
+        assert!(day2::manual::parse_games(line, None).is_err());
+        assert!(day2::chumsky::parse_games(line, None).is_err());
+        assert!(day2::streaming::parse_games(line.as_bytes(), None).is_err());
+    }
+}
+
+mod inference {
+    use aoc2023::day2::{
+        self,
+        inference::{log_likelihood, most_likely_bag, most_likely_shared_bag},
+        ColorSet, Palette,
+    };
+
+    #[test]
+    fn single_game() {
+        let games = day2::parse_games("Game 1: 1 red; 1 red; 1 blue", None).unwrap();
+        let palette = Palette::new(["red", "blue"]);
+        // (r/N)^2 (b/N) peaks at two reds and a blue among bags of up to three cubes
+        let estimate = most_likely_bag(&games[0], &palette, 3).unwrap();
+        assert_eq!(
+            estimate.bag,
+            ColorSet::from_counts([("red", 2), ("blue", 1)])
+        );
+        assert!((estimate.log_likelihood - (4.0_f64 / 27.0).ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn impossible_bag() {
+        let games = day2::parse_games("Game 1: 3 red, 1 blue", None).unwrap();
+        let bag = ColorSet::new(2, 0, 5);
+        assert_eq!(log_likelihood(&games[0], &bag), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn uncovered_colors() {
+        // no bag over the palette can hold a purple cube
+        let games = day2::parse_games("Game 1: 2 purple", None).unwrap();
+        assert!(most_likely_bag(&games[0], &Palette::rgb(), 5).is_none());
+    }
+
+    #[test]
+    fn shared_bag() {
+        let input = "Game 1: 2 red\nGame 2: 1 blue\n";
+        let games = day2::parse_games(input, None).unwrap();
+        let palette = Palette::new(["red", "blue"]);
+        let estimate = most_likely_shared_bag(&games, &palette, 3).unwrap();
+        assert_eq!(
+            estimate.bag,
+            ColorSet::from_counts([("red", 2), ("blue", 1)])
+        );
+        assert!(most_likely_shared_bag(&games, &palette, 2).is_none());
+    }
+}
+
+mod feasibility {
+    use super::SAMPLE;
+    use aoc2023::day2::{self, feasibility::Feasibility, ColorSet};
+
+    #[test]
+    fn many_bags() {
+        let games = day2::parse_games(SAMPLE, None).unwrap();
+        let feasibility = Feasibility::new(&games);
+        let bags = [
+            ColorSet::new(12, 13, 14),
+            ColorSet::new(4, 2, 6),
+            ColorSet::default(),
+        ];
+        let possible = feasibility.possible_for_each(&bags);
+        assert_eq!(possible, [vec![1, 2, 5], vec![1], vec![]]);
+    }
+
+    #[test]
+    fn smallest_bag() {
+        let games = day2::parse_games(SAMPLE, None).unwrap();
+        let feasibility = Feasibility::new(&games);
+        let bag = feasibility.smallest_bag();
+        assert_eq!(bag, ColorSet::new(20, 13, 15));
+        assert_eq!(feasibility.possible(&bag), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn frontier() {
+        let games = day2::parse_games(SAMPLE, None).unwrap();
+        let feasibility = Feasibility::new(&games);
+        // covers are 4/2/6, 1/3/4, 20/13/6, 14/3/15 and 6/3/2 (red/green/blue)
+        let frontier = feasibility.frontier(1);
+        assert_eq!(
+            frontier,
+            [
+                ColorSet::new(6, 3, 2),
+                ColorSet::new(1, 3, 4),
+                ColorSet::new(4, 2, 6),
+            ]
+        );
+        for bag in &frontier {
+            assert_eq!(feasibility.possible(bag).len(), 1);
+        }
+        let frontier = feasibility.frontier(2);
+        for bag in &frontier {
+            assert_eq!(feasibility.possible(bag).len(), 2);
+        }
+        assert_eq!(feasibility.frontier(5), [feasibility.smallest_bag()]);
+        assert!(feasibility.frontier(6).is_empty());
+    }
+}
+
+mod differential {
+    use crate::common::Rng;
+    use aoc2023::day2::{chumsky, manual, streaming, Game};
+
+    const CASES: usize = 5000;
+    const COLORS: [&str; 5] = ["red", "green", "blue", "cyan", "ö"];
+    // characters that are likely to matter to at least one of the parsers
+    const NOISE: &[u8] = b"  ,;:09Gamerdx\t";
+    // counts that overflow when a colour is repeated in a draw, or just fit
+    const LARGE: [usize; 4] = [usize::MAX, usize::MAX / 2, usize::MAX / 2 + 1, 1];
+
+    fn valid_line(rng: &mut Rng) -> String {
+        line(rng, &COLORS, |rng| rng.below(20) + 1)
+    }
+
+    // few colours, so they repeat within a draw, with counts near the limit
+    fn large_line(rng: &mut Rng) -> String {
+        line(rng, &COLORS[..2], |rng| rng.pick(&LARGE))
+    }
+
+    fn line(rng: &mut Rng, colors: &[&str], count: impl Fn(&mut Rng) -> usize) -> String {
+        let draws: Vec<String> = (0..rng.below(4) + 1)
+            .map(|_| {
+                let cubes: Vec<String> = (0..rng.below(3) + 1)
+                    .map(|_| format!("{} {}", count(rng), rng.pick(colors)))
+                    .collect();
+                cubes.join(", ")
+            })
+            .collect();
+        format!("Game {}: {}", rng.below(100) + 1, draws.join("; "))
+    }
+
+    fn mutate(rng: &mut Rng, line: &str) -> String {
+        let mut bytes = line.as_bytes().to_vec();
+        for _ in 0..rng.below(3) + 1 {
+            let pos = rng.below(bytes.len() + 1);
+            match rng.below(3) {
+                0 if pos < bytes.len() => {
+                    bytes.remove(pos);
+                }
+                1 => bytes.insert(pos, rng.pick(NOISE)),
+                _ if pos + 1 < bytes.len() => bytes.swap(pos, pos + 1),
+                _ => {}
+            }
+        }
+        // mutations may split a multi-byte character
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    // every game parsed, which is none at all for an empty line
+    type Outcomes = [Option<Vec<Game>>; 3];
+
+    fn outcomes(line: &str) -> Outcomes {
+        [
+            manual::parse_games(line, None).ok(),
+            chumsky::parse_games(line, None).ok(),
+            streaming::parse_games(line.as_bytes(), None).ok(),
+        ]
+    }
+
+    fn agree(outcomes: &Outcomes) -> bool {
+        outcomes.iter().all(|outcome| *outcome == outcomes[0])
+    }
+
+    /// Removes characters from `line` for as long as the parsers still disagree.
+    fn shrink(mut line: String) -> String {
+        'outer: loop {
+            for (pos, ch) in line.char_indices() {
+                let mut candidate = line.clone();
+                candidate.replace_range(pos..pos + ch.len_utf8(), "");
+                if !agree(&outcomes(&candidate)) {
+                    line = candidate;
+                    continue 'outer;
+                }
+            }
+            return line;
+        }
+    }
+
+    fn check(line: String) {
+        if !agree(&outcomes(&line)) {
+            let line = shrink(line);
+            let [manual, chumsky, streaming] = outcomes(&line);
+            panic!(
+                "parsers disagree on {line:?}\n\
+                 manual: {manual:?}\nchumsky: {chumsky:?}\nstreaming: {streaming:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn valid_lines() {
+        let mut rng = Rng(0x2023_1202);
+        for _ in 0..CASES {
+            let line = valid_line(&mut rng);
+            assert!(outcomes(&line)[0].is_some(), "rejected {line:?}");
+            check(line);
+        }
+    }
+
+    #[test]
+    fn large_counts() {
+        let mut rng = Rng(0x2023_1202);
+        for _ in 0..CASES {
+            let line = large_line(&mut rng);
+            check(mutate(&mut rng, &line));
+            check(line);
+        }
+    }
+
+    #[test]
+    fn mutated_lines() {
+        let mut rng = Rng(0x2023_1202);
+        for _ in 0..CASES {
+            let line = valid_line(&mut rng);
+            check(mutate(&mut rng, &line));
+        }
+    }
+}
@@ -41,3 +41,240 @@ fn part2_input() {
     let result = day3::part2(input).unwrap();
     assert_eq!(result, 84584891);
 }
+
+#[test]
+fn adjacency_queries() {
+    let schematic = day3::Schematic::parse(SAMPLE.as_bytes()).unwrap();
+    assert_eq!(schematic.symbols().len(), 6);
+
+    let loose: Vec<_> = schematic
+        .numbers_touching(0..1)
+        .map(|num| num.value)
+        .collect();
+    assert_eq!(loose, [114, 58]);
+    assert_eq!(schematic.numbers_touching(2..).count(), 0);
+
+    let star = schematic.symbol_at(1, 3).unwrap();
+    assert_eq!(schematic.symbols()[star].ch, '*');
+    let around: Vec<_> = schematic
+        .numbers_of(star)
+        .iter()
+        .map(|&idx| schematic.numbers()[idx].value)
+        .collect();
+    assert_eq!(around, [467, 35]);
+
+    let lonely: Vec<_> = schematic
+        .symbols_with(1)
+        .map(|idx| schematic.symbols()[idx].ch)
+        .collect();
+    assert_eq!(lonely, ['#', '*', '+', '$']);
+
+    let sums = schematic.symbol_sums();
+    assert_eq!(sums[star], 502);
+    assert_eq!(sums.iter().sum::<usize>(), 4361);
+
+    let num = schematic.number_at(2, 3).unwrap();
+    assert_eq!(schematic.numbers()[num].value, 35);
+    assert_eq!(schematic.symbols_of(num), [star]);
+}
+
+#[test]
+fn gear_rules() {
+    use day3::{Combine, GearRule};
+
+    let schematic = day3::Schematic::parse(SAMPLE.as_bytes()).unwrap();
+    let ratios = |rule| -> Vec<_> { schematic.gears_with(rule).map(|(_, ratio)| ratio).collect() };
+    assert_eq!(ratios(GearRule::default()), [16345, 451490]);
+    assert_eq!(
+        ratios(GearRule::new(['*'], 1..=3, Combine::Max)),
+        [467, 617, 755]
+    );
+    // every number touches exactly one symbol, so this adds up to part 1
+    let any = GearRule::new("*#+$".chars(), 1..=usize::MAX, Combine::Sum);
+    assert_eq!(ratios(any).iter().sum::<usize>(), 4361);
+    let spread = |values: &[usize]| values.iter().max().unwrap() - values.iter().min().unwrap();
+    assert_eq!(
+        ratios(GearRule::new(['*'], 2..=2, Combine::Custom(spread))),
+        [432, 157]
+    );
+}
+
+#[test]
+fn render() {
+    use day3::{GearRule, Highlight};
+
+    let schematic = day3::Schematic::parse(SAMPLE.as_bytes()).unwrap();
+    let highlights = schematic.highlights(GearRule::default());
+    assert_eq!(highlights[(0, 0)], Highlight::PartNumber);
+    assert_eq!(highlights[(0, 5)], Highlight::LooseNumber);
+    assert_eq!(highlights[(1, 3)], Highlight::Gear);
+    assert_eq!(highlights[(4, 3)], Highlight::Symbol);
+    assert_eq!(highlights[(0, 3)], Highlight::Empty);
+
+    let ansi = schematic.to_ansi(GearRule::default());
+    assert!(ansi.starts_with("\x1b[32m467\x1b[0m\x1b[2m..\x1b[0m\x1b[31m114\x1b[0m"));
+    let mut plain = String::new();
+    let mut chars = ansi.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            chars.by_ref().find(|&ch| ch == 'm');
+        } else {
+            plain.push(ch);
+        }
+    }
+    assert_eq!(plain, SAMPLE);
+
+    let html = schematic.to_html(GearRule::default());
+    assert!(html.contains(concat!(
+        "<span class=\"empty\">.....</span>",
+        "<span class=\"symbol\">+</span>",
+        "<span class=\"empty\">.</span>",
+        "<span class=\"loose\">58</span>",
+    )));
+    assert_eq!(html.matches("class=\"gear\"").count(), 2);
+}
+
+#[test]
+fn unicode_symbols() {
+    let input = "é12.\n..€3\n";
+    let schematic = day3::Schematic::parse(input.as_bytes()).unwrap();
+    assert_eq!(schematic.width(), 4);
+    let symbols: Vec<_> = schematic
+        .symbols()
+        .iter()
+        .map(|sym| (sym.ch, sym.row, sym.col))
+        .collect();
+    assert_eq!(symbols, [('é', 0, 0), ('€', 1, 2)]);
+    let spans: Vec<_> = schematic
+        .numbers()
+        .iter()
+        .map(|num| (num.value, num.span.start, num.span.end))
+        .collect();
+    assert_eq!(spans, [(12, 1, 3), (3, 3, 4)]);
+    assert_eq!(day3::part1(input.as_bytes()).unwrap(), 15);
+}
+
+#[test]
+fn invalid_rows() {
+    let input = "467..\n...*..\n.99999999999999999999\n.*\n";
+    let err = day3::part1(input.as_bytes()).unwrap_err();
+    let related: Vec<_> = err.related().unwrap().map(|e| e.to_string()).collect();
+    assert_eq!(
+        related,
+        [
+            "row 1 has 6 cells, expected 5",
+            "number is too large",
+            "row 2 has 21 cells, expected 5",
+            "row 3 has 2 cells, expected 5",
+        ]
+    );
+    let labels: Vec<_> = err
+        .related()
+        .unwrap()
+        .flat_map(|e| e.labels().unwrap())
+        .map(|label| (label.offset(), label.len()))
+        .collect();
+    assert_eq!(labels, [(6, 6), (14, 20), (13, 21), (35, 2)]);
+}
+
+#[test]
+fn edits() {
+    let mut schematic = day3::Schematic::parse(SAMPLE.as_bytes()).unwrap();
+    // joins 617 and the gear next to it into a single number
+    schematic.set(4, 3, '0').unwrap();
+    assert_eq!(schematic.number_at(4, 0), schematic.number_at(4, 3));
+    assert_eq!(schematic.part_number_sum(), 4361 - 617);
+    // splits 755 and 598 off their gear
+    schematic.clear(8, 5).unwrap();
+    assert_eq!(schematic.part_number_sum(), 4361 - 617 - 755 - 598);
+    assert_eq!(schematic.gear_ratio_sum(), 16345);
+    // splits 467 in two, leaving only the 7 next to the gear
+    schematic.set(0, 1, '.').unwrap();
+    assert_eq!(schematic.gear_ratio_sum(), 7 * 35);
+    assert!(schematic.set(10, 0, '1').is_err());
+}
+
+mod editing {
+    use crate::common::Rng;
+    use aoc2023::day3::Schematic;
+    use std::collections::BTreeSet;
+
+    const CASES: usize = 200;
+    const EDITS: usize = 50;
+    // mostly empty cells, so that numbers are split and merged often
+    const CHARS: &[u8] = b".....0123456789*#";
+
+    type Cell = (usize, usize);
+
+    /// Everything that should survive an edit, keyed by position rather
+    /// than by index, since indices change with every edit.
+    #[derive(Debug, PartialEq)]
+    struct Shape {
+        numbers: BTreeSet<(Cell, usize, usize, BTreeSet<Cell>)>,
+        symbols: BTreeSet<(Cell, char, BTreeSet<Cell>)>,
+        part_sum: usize,
+        gear_sum: usize,
+    }
+
+    fn shape(schematic: &Schematic) -> Shape {
+        let numbers = schematic.numbers();
+        let symbols = schematic.symbols();
+        let number_cell = |idx: usize| (numbers[idx].span.row, numbers[idx].span.start);
+        let symbol_cell = |idx: usize| (symbols[idx].row, symbols[idx].col);
+        Shape {
+            numbers: (0..numbers.len())
+                .map(|idx| {
+                    let adjacent = schematic.symbols_of(idx).iter().copied();
+                    let span = &numbers[idx].span;
+                    let len = span.end - span.start;
+                    let value = numbers[idx].value;
+                    (
+                        number_cell(idx),
+                        len,
+                        value,
+                        adjacent.map(symbol_cell).collect(),
+                    )
+                })
+                .collect(),
+            symbols: (0..symbols.len())
+                .map(|idx| {
+                    let adjacent = schematic.numbers_of(idx).iter().copied();
+                    let ch = symbols[idx].ch;
+                    (symbol_cell(idx), ch, adjacent.map(number_cell).collect())
+                })
+                .collect(),
+            part_sum: schematic.part_number_sum(),
+            gear_sum: schematic.gear_ratio_sum(),
+        }
+    }
+
+    fn text(rows: &[Vec<char>]) -> String {
+        rows.iter()
+            .map(|row| row.iter().collect::<String>() + "\n")
+            .collect()
+    }
+
+    #[test]
+    fn matches_full_rebuild() {
+        let mut rng = Rng(0x2023_1203);
+        for case in 0..CASES {
+            let (height, width) = (rng.below(6) + 1, rng.below(10) + 1);
+            let mut rows: Vec<Vec<char>> = (0..height)
+                .map(|_| (0..width).map(|_| rng.pick(CHARS) as char).collect())
+                .collect();
+            let mut schematic = Schematic::parse(text(&rows).as_bytes()).unwrap();
+            for _ in 0..EDITS {
+                let (row, col, ch) = (rng.below(height), rng.below(width), rng.pick(CHARS) as char);
+                let before = text(&rows);
+                schematic.set(row, col, ch).unwrap();
+                rows[row][col] = ch;
+                let rebuilt = Schematic::parse(text(&rows).as_bytes()).unwrap();
+                assert_eq!(
+                    shape(&schematic),
+                    shape(&rebuilt),
+                    "case {case}: writing {ch:?} at ({row}, {col}) into\n{before}"
+                );
+            }
+        }
+    }
+}
@@ -38,3 +38,201 @@ fn part2_input() {
     let result = day4::part2(&input).unwrap();
     assert_eq!(result, 14427616);
 }
+
+#[test]
+fn copies() {
+    let copies = day4::copies(SAMPLE).unwrap();
+    assert_eq!(copies, [(1, 1), (2, 2), (3, 4), (4, 8), (5, 14), (6, 1)]);
+}
+
+#[test]
+fn copies_overflow() {
+    // every card wins the next two, so counts grow like the Fibonacci numbers
+    let input: String = (1..=200)
+        .map(|id| match id {
+            ..=198 => format!("Card {id}: 1 2 | 1 2\n"),
+            _ => format!("Card {id}: 1 2 | 3 4\n"),
+        })
+        .collect();
+    let err = day4::part2(&input).unwrap_err();
+    assert!(err.to_string().starts_with("copy count overflowed at card"));
+
+    let mut cascade = day4::Cascade::new();
+    let counts: Vec<_> = (0..8).map(|_| cascade.push(2).unwrap()).collect();
+    assert_eq!(counts, [1, 2, 4, 7, 12, 20, 33, 54]);
+}
+
+#[test]
+fn invalid_table() {
+    let input = indoc! { r#"
+		Card 1: 41 48 41 | 41 48  6
+		Card 3: 13 32 20 | 61 30 68
+		Card 3:  1 21 | 69 82 63
+		Card 4:  8 92 73 | 8 92 73
+	"# };
+    let err = day4::part2(input).unwrap_err();
+    let related: Vec<_> = err
+        .related()
+        .unwrap()
+        .map(|err| {
+            let label = err.labels().unwrap().next().unwrap();
+            let span = &input[label.offset()..label.offset() + label.len()];
+            format!("{err} @ {}", span.trim_end())
+        })
+        .collect();
+    assert_eq!(
+        related,
+        [
+            "number 41 is repeated @ 41",
+            "expected card 2, found card 3 @ 3",
+            "card 3 appears more than once @ 3",
+            "card 3 has 2 winning numbers, expected 3 @ 1 21",
+            "card 4 wins 3 cards, but only 0 follow it @ Card 4:  8 92 73 | 8 92 73",
+        ]
+    );
+    assert!(day4::part1(input).is_err());
+}
+
+#[test]
+fn wins_past_the_end() {
+    use day4::Winnings;
+
+    // card 2 wins a card under the puzzle's rules, but there is none left
+    let input = indoc! { r#"
+		Card 1: 1 2 | 3 4
+		Card 2: 1 2 | 1 9
+	"# };
+    assert!(day4::part2(input).is_err());
+    assert_eq!(day4::part1(input).unwrap(), 1);
+    let cards = day4::parse_cards(input).unwrap();
+    let copies = day4::copies_with(&cards, Winnings::Itself).unwrap();
+    assert_eq!(copies, [(1, 1), (2, 2)]);
+    let copies = day4::copies_with(&cards, Winnings::Offsets(&[0])).unwrap();
+    assert_eq!(copies, [(1, 1), (2, 1)]);
+}
+
+#[test]
+fn scoring_rules() {
+    use day4::Scoring;
+
+    let cards = day4::parse_cards(SAMPLE).unwrap();
+    let score = |scoring: Scoring| -> usize {
+        cards
+            .iter()
+            .map(|card| card.points(&scoring).unwrap())
+            .sum()
+    };
+    assert_eq!(score(Scoring::Doubling), 13);
+    assert_eq!(score(Scoring::Linear), 9);
+    assert_eq!(score(Scoring::Fibonacci), 10);
+    assert_eq!(score(Scoring::Custom(&|matches| matches * matches)), 25);
+
+    // points that don't fit in a usize are reported rather than wrapping
+    assert_eq!(Scoring::Doubling.points(64), Some(1 << 63));
+    assert_eq!(Scoring::Doubling.points(65), None);
+    assert_eq!(Scoring::Fibonacci.points(10), Some(89));
+    assert_eq!(Scoring::Fibonacci.points(100), None);
+    let numbers: String = (1..=70).map(|num| format!(" {num}")).collect();
+    let input = format!("Card 1:{numbers} |{numbers}\n");
+    let err = day4::part1(&input).unwrap_err();
+    assert_eq!(err.to_string(), "points overflowed at card 1");
+}
+
+#[test]
+fn cascade_rules() {
+    use day4::Winnings;
+
+    let cards = day4::parse_cards(SAMPLE).unwrap();
+    let total = |winnings| -> u128 {
+        let copies = day4::copies_with(&cards, winnings).unwrap();
+        copies.into_iter().map(|(_, count)| count).sum()
+    };
+    assert_eq!(total(Winnings::Next), 30);
+    assert_eq!(total(Winnings::Offsets(&[1, 2, 3, 4])), 30);
+    // cards 1 to 4 each win the card two places down
+    assert_eq!(total(Winnings::Offsets(&[2])), 12);
+    assert_eq!(total(Winnings::Offsets(&[0, 0])), 6);
+    // wins past the end of the table are dropped without making room for them
+    assert_eq!(total(Winnings::Offsets(&[usize::MAX, 1_000_000_000, 5])), 7);
+    let trace = day4::Trace::new(&cards, Winnings::Offsets(&[usize::MAX])).unwrap();
+    assert_eq!(trace.copies(2), Some(1));
+    assert_eq!(total(Winnings::Itself), 15);
+}
+
+#[test]
+fn trace() {
+    use day4::{Trace, Winnings};
+
+    let cards = day4::parse_cards(SAMPLE).unwrap();
+    let trace = Trace::new(&cards, Winnings::Next).unwrap();
+    assert_eq!(trace.copies(5), Some(14));
+    assert_eq!(trace.sources(5), Some(&[(1, 1), (3, 4), (4, 8)][..]));
+    assert_eq!(trace.sources(6), Some(&[][..]));
+    assert_eq!(trace.copies(7), None);
+
+    // the trace agrees with the cascade whatever the rules
+    let rules = [
+        Winnings::Next,
+        Winnings::Offsets(&[0, 1, 1]),
+        Winnings::Offsets(&[3, 1]),
+        Winnings::Itself,
+    ];
+    for winnings in rules {
+        let trace = Trace::new(&cards, winnings).unwrap();
+        let copies = day4::copies_with(&cards, winnings).unwrap();
+        for (id, count) in copies {
+            assert_eq!(trace.copies(id), Some(count), "{winnings:?}");
+        }
+    }
+    let trace = Trace::new(&cards, Winnings::Offsets(&[0, 1, 1])).unwrap();
+    assert_eq!(trace.sources(2), Some(&[(1, 2)][..]));
+    let trace = Trace::new(&cards, Winnings::Itself).unwrap();
+    assert_eq!(trace.sources(1), Some(&[(1, 4)][..]));
+
+    // ids don't have to start from 1
+    let trace = Trace::new(&cards[2..], Winnings::Next).unwrap();
+    assert_eq!(trace.ids(), [3, 4, 5, 6]);
+    assert_eq!(trace.copies(3), Some(1));
+    assert_eq!(trace.copies(5), Some(4));
+    assert_eq!(trace.sources(6), Some(&[][..]));
+    assert_eq!(trace.copies(1), None);
+}
+
+#[test]
+fn export_trace() {
+    let cards = day4::parse_cards(SAMPLE).unwrap();
+    let trace = day4::Trace::new(&cards[..3], day4::Winnings::Next).unwrap();
+    let json = indoc! { r#"
+		{
+		  "cards": [
+		    { "id": 1, "copies": 1, "sources": {} },
+		    { "id": 2, "copies": 2, "sources": { "1": 1 } },
+		    { "id": 3, "copies": 4, "sources": { "1": 1, "2": 2 } }
+		  ]
+		}
+	"# };
+    assert_eq!(trace.to_json(), json);
+    let dot = indoc! { r#"
+		digraph cascade {
+		    1 [label="Card 1\n1 copy"];
+		    2 [label="Card 2\n2 copies"];
+		    3 [label="Card 3\n4 copies"];
+		    1 -> 2 [label="1"];
+		    1 -> 3 [label="1"];
+		    2 -> 3 [label="2"];
+		}
+	"# };
+    assert_eq!(trace.to_dot(), dot);
+}
+
+#[test]
+fn large_numbers() {
+    // numbers from 128 on don't fit in a bitset, so these mix both representations
+    let input = indoc! { r#"
+		Card 1: 127 4 | 127 128 3
+		Card 2: 5 300 | 5 299 7
+		Card 3: 1 6 | 2 3 4
+	"# };
+    assert_eq!(day4::part1(input).unwrap(), 2);
+    assert_eq!(day4::part2(input).unwrap(), 6);
+}
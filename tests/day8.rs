@@ -1,6 +1,6 @@
 mod common;
 
-use aoc2023::day8;
+use aoc2023::day8::{self, Matcher};
 use indoc::indoc;
 
 const SAMPLE1: &str = indoc! { r#"
@@ -65,3 +65,119 @@ fn part2_input() {
     let result = day8::part2(&input).unwrap();
     assert_eq!(result, 21083806112641); // wow
 }
+
+#[test]
+fn long_labels() -> miette::Result<()> {
+    let input = indoc! { r#"
+		LR
+
+		start = (middle, start)
+		middle = (start, goal)
+		goal = (goal, goal)
+	"# };
+    let maze = day8::Maze::parse(input)?;
+    assert_eq!(maze.len(), 3);
+    let start = maze.id("start").unwrap();
+    assert_eq!(maze.label(start), "start");
+    assert_eq!(maze.walk(start, &Matcher::Label("goal"))?.steps, 2);
+    Ok(())
+}
+
+#[test]
+fn custom_predicates() -> miette::Result<()> {
+    let maze = day8::Maze::parse(SAMPLE3)?;
+    let starts: Vec<_> = maze
+        .starts(&Matcher::Pattern("22*"))
+        .map(|node| maze.label(node))
+        .collect();
+    assert_eq!(starts, ["22A", "22B", "22C", "22Z"]);
+    Ok(())
+}
+
+#[test]
+fn undefined_node() {
+    let input = indoc! { r#"
+		L
+
+		AAA = (BBB, AAA)
+	"# };
+    assert!(day8::Maze::parse(input).is_err());
+}
+
+#[test]
+fn export_json() -> miette::Result<()> {
+    let maze = day8::Maze::parse(SAMPLE2)?;
+    let expected = indoc! { r#"
+		{
+		  "instructions": "LLR",
+		  "nodes": {
+		    "AAA": ["BBB", "BBB"],
+		    "BBB": ["AAA", "ZZZ"],
+		    "ZZZ": ["ZZZ", "ZZZ"]
+		  }
+		}
+	"# };
+    assert_eq!(maze.to_json(), expected);
+    Ok(())
+}
+
+#[test]
+fn export_dot() -> miette::Result<()> {
+    let maze = day8::Maze::parse(SAMPLE2)?;
+    let dot = maze.to_dot(&Matcher::Label("AAA"), &Matcher::Label("ZZZ"), true);
+    let expected = indoc! { r#"
+		digraph maze {
+		    "AAA" [style=filled, fillcolor=palegreen];
+		    "ZZZ" [style=filled, fillcolor=lightcoral];
+		    "AAA" -> "BBB" [label="L"];
+		    "AAA" -> "BBB" [label="R"];
+		    "BBB" -> "AAA" [label="L"];
+		    "BBB" -> "ZZZ" [label="R"];
+		    "ZZZ" -> "ZZZ" [label="L", color=blue, penwidth=2];
+		    "ZZZ" -> "ZZZ" [label="R", color=blue, penwidth=2];
+		}
+	"# };
+    assert_eq!(dot, expected);
+    Ok(())
+}
+
+#[test]
+fn walk_details() -> miette::Result<()> {
+    let maze = day8::Maze::parse(SAMPLE2)?;
+    let start = maze.id("AAA").unwrap();
+    let walk = maze.walk(start, &Matcher::Label("ZZZ"))?;
+    assert_eq!(walk.steps, 6);
+    assert_eq!(walk.offset, 0);
+    let path: Vec<_> = walk.path.iter().map(|&node| maze.label(node)).collect();
+    assert_eq!(path, ["AAA", "BBB", "AAA", "BBB", "AAA", "BBB", "ZZZ"]);
+    Ok(())
+}
+
+#[test]
+fn walk_from_set() -> miette::Result<()> {
+    let maze = day8::Maze::parse(SAMPLE3)?;
+    let starts = Matcher::Set(["11A", "22A"].into());
+    let walks = maze.walk_all(&starts, &Matcher::Pattern("??Z"))?;
+    let summary: Vec<_> = walks
+        .iter()
+        .map(|walk| (maze.label(walk.start()), walk.steps, walk.offset))
+        .collect();
+    assert_eq!(summary, [("11A", 2, 0), ("22A", 3, 1)]);
+    Ok(())
+}
+
+#[test]
+fn unreachable_goal() {
+    let input = indoc! { r#"
+		LR
+
+		AAA = (BBB, AAA)
+		BBB = (AAA, AAA)
+		ZZZ = (ZZZ, ZZZ)
+	"# };
+    let err = day8::part1(input).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "no goal is reachable from AAA, trapped in cycle AAA -> BBB -> AAA"
+    );
+}